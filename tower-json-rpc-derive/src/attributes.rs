@@ -24,7 +24,7 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use std::{fmt, iter};
+use std::{fmt, iter, time::Duration};
 
 use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
 use syn::parse::{Parse, ParseStream, Parser};
@@ -41,8 +41,9 @@ pub(crate) struct Argument {
 	pub tokens: TokenStream2,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum ParamKind {
+	#[default]
 	Array,
 	Map,
 }
@@ -52,6 +53,17 @@ pub struct NameMapping {
 	pub mapped: Option<String>,
 }
 
+/// Parsed `#[deprecated]` attribute of an RPC method or subscription.
+///
+/// The attribute itself is re-emitted on the generated items, while `since` and `note`
+/// are made available at runtime.
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+	pub attr: Attribute,
+	pub since: Option<String>,
+	pub note: Option<String>,
+}
+
 pub struct Bracketed<T> {
 	pub list: Punctuated<T, Token![,]>,
 }
@@ -60,7 +72,8 @@ pub type Aliases = Bracketed<LitStr>;
 
 impl Parse for Argument {
 	fn parse(input: ParseStream) -> syn::Result<Self> {
-		let label = input.parse()?;
+		// `parse_any` also accepts keywords, such as `crate`.
+		let label = input.call(syn::ext::IdentExt::parse_any)?;
 
 		let mut scope = 0usize;
 
@@ -168,6 +181,65 @@ impl AttributeMeta {
 	}
 }
 
+impl Deprecation {
+	/// Parses any of the `#[deprecated]`, `#[deprecated = "note"]` and
+	/// `#[deprecated(since = "..", note = "..")]` forms.
+	pub fn parse(attr: &Attribute) -> syn::Result<Deprecation> {
+		let mut since = None;
+		let mut note = None;
+
+		match &attr.meta {
+			syn::Meta::Path(_) => {}
+			syn::Meta::NameValue(meta) => match &meta.value {
+				syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => note = Some(lit.value()),
+				value => return Err(Error::new(value.span(), "Expected a string literal")),
+			},
+			syn::Meta::List(_) => attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("since") {
+					let lit = meta.value()?.parse::<LitStr>()?;
+					check_date(&lit)?;
+					since = Some(lit.value());
+				} else if meta.path.is_ident("note") {
+					note = Some(meta.value()?.parse::<LitStr>()?.value());
+				} else {
+					return Err(meta.error("Unknown argument, expected one of: `since`, `note`"));
+				}
+				Ok(())
+			})?,
+		}
+
+		Ok(Deprecation { attr: attr.clone(), since, note })
+	}
+}
+
+/// Rejects a `since` written as a `YYYY-MM-DD` date that doesn't exist, as the server turns dates
+/// into the `Deprecation` header. Other values, such as versions, are left alone.
+fn check_date(lit: &LitStr) -> syn::Result<()> {
+	let value = lit.value();
+	let parts: Vec<&str> = value.split('-').collect();
+	let [year, month, day] = parts[..] else {
+		return Ok(());
+	};
+	let digits = |part: &str, len: usize| part.len() == len && part.bytes().all(|byte| byte.is_ascii_digit());
+	if !digits(year, 4) || !digits(month, 2) || !digits(day, 2) {
+		return Ok(());
+	}
+
+	let (year, month, day) = (year.parse::<u32>().unwrap(), month.parse::<u32>().unwrap(), day.parse::<u32>().unwrap());
+	let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+	let days = match month {
+		2 if leap => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		1..=12 => 31,
+		_ => 0,
+	};
+	if !(1..=days).contains(&day) {
+		return Err(Error::new(lit.span(), format!("Invalid date {value:?}; expected a `YYYY-MM-DD` date or a version")));
+	}
+	Ok(())
+}
+
 pub(crate) struct MissingArgument<'a>(Span, &'a str);
 
 impl fmt::Display for MissingArgument<'_> {
@@ -211,6 +283,85 @@ impl Argument {
 	pub fn string(self) -> syn::Result<String> {
 		self.value::<LitStr>().map(|lit| lit.value())
 	}
+
+	/// Asserts that the argument is `key = "duration"`, such as `"500ms"` or `"5s"`, and parses the duration
+	pub fn duration(self) -> syn::Result<Duration> {
+		let lit = self.value::<LitStr>()?;
+		let value = lit.value();
+
+		parse_duration(&value).ok_or_else(|| {
+			Error::new(lit.span(), format!("Invalid duration {value:?}; expected a number followed by `us`, `ms`, `s`, `m` or `h`"))
+		})
+	}
+
+	/// Asserts that the argument is `key = "requests/period"`, such as `"10/s"` or `"100/5m"`, and parses
+	/// the rate limit
+	pub fn rate_limit(self) -> syn::Result<RateLimit> {
+		let lit = self.value::<LitStr>()?;
+		let value = lit.value();
+
+		let rate_limit = value.split_once('/').and_then(|(requests, period)| {
+			let requests = requests.parse().ok().filter(|requests| *requests > 0)?;
+			// A bare unit stands for one of it, so `10/s` is `10/1s`.
+			let period = match period.starts_with(|c: char| c.is_ascii_digit()) {
+				true => parse_duration(period)?,
+				false => parse_duration(&format!("1{period}"))?,
+			};
+			(!period.is_zero()).then_some(RateLimit { requests, period })
+		});
+
+		rate_limit.ok_or_else(|| {
+			Error::new(
+				lit.span(),
+				format!("Invalid rate limit {value:?}; expected a number of requests per duration, such as `10/s` or `100/5m`"),
+			)
+		})
+	}
+
+	/// Asserts that the argument is `key = units` or `key = path::to::function` and parses the cost
+	pub fn cost(self) -> syn::Result<Cost> {
+		match self.value::<syn::Expr>()? {
+			syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(units), .. }) => {
+				units.base10_parse::<u64>()?;
+				Ok(Cost::Fixed(units))
+			}
+			syn::Expr::Path(syn::ExprPath { path, qself: None, .. }) => Ok(Cost::Dynamic(path)),
+			value => Err(Error::new(
+				value.span(),
+				"Expected a number of compute units or the path of a function computing them",
+			)),
+		}
+	}
+}
+
+/// The `cost` of a method: a number of compute units, or a function computing them from the params.
+#[derive(Debug, Clone)]
+pub enum Cost {
+	Fixed(syn::LitInt),
+	Dynamic(syn::Path),
+}
+
+/// The `rate_limit` of a method: at most `requests` calls every `period`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+	pub requests: u32,
+	pub period: Duration,
+}
+
+/// Parses durations such as `"500us"`, `"500ms"`, `"5s"`, `"1m"` or `"2h"`.
+fn parse_duration(value: &str) -> Option<Duration> {
+	let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+	let (amount, unit) = value.split_at(split);
+
+	let micros = match (amount.parse::<u64>(), unit) {
+		(Ok(amount), "us") => Some(amount),
+		(Ok(amount), "ms") => amount.checked_mul(1_000),
+		(Ok(amount), "s") => amount.checked_mul(1_000_000),
+		(Ok(amount), "m") => amount.checked_mul(60_000_000),
+		(Ok(amount), "h") => amount.checked_mul(3_600_000_000),
+		_ => None,
+	};
+	micros.map(Duration::from_micros)
 }
 
 pub(crate) fn optional<T, F>(arg: Result<Argument, MissingArgument>, transform: F) -> syn::Result<Option<T>>
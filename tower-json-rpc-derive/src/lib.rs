@@ -34,6 +34,7 @@ use rpc_macro::RpcDescription;
 
 mod attributes;
 mod render_client;
+mod render_serde;
 mod render_server;
 mod rpc_macro;
 
@@ -65,7 +66,7 @@ mod rpc_macro;
 /// To use the `FooClient`, just import it in the context. To use the server, the `FooServer` trait must be implemented
 /// on your type first.
 ///
-/// Note: you need to import the `jsonrpsee` façade crate in your code for the macro to work properly.
+/// Note: the generated code only depends on the `tower_json_rpc` crate, see the `crate` argument.
 ///
 /// ## Prerequisites
 ///
@@ -151,6 +152,16 @@ mod rpc_macro;
 ///   implementation.
 /// - `client_bounds`: replace *all* auto-generated trait bounds with the user-defined ones for the client
 ///   implementation.
+/// - `serde`: implement `Serialize` and `Deserialize` for `<Trait>Request` and, with `client`, `<Trait>Response`.
+///   Requests are (de)serialized as `{ "method": ..., "params": ... }` and responses as
+///   `{ "method": ..., "result": ... }`, following `param_kind`, `rename` and aliases.
+/// - `timeout`: default `timeout` of the methods, such as `timeout = "30s"`.
+/// - `requires`: default `requires` of the methods, such as `requires = "admin"`.
+/// - `rate_limit`: default `rate_limit` of the methods, such as `rate_limit = "100/s"`.
+/// - `cost`: default `cost` of the methods, such as `cost = 1`.
+/// - `crate`: path to the `tower_json_rpc` crate, for example `crate = "my_facade::rpc"` when it is
+///   re-exported under another name. Defaults to `::tower_json_rpc`. All other dependencies of the
+///   generated code are reached through this crate, so they don't have to be direct dependencies.
 ///
 /// **Trait requirements:**
 ///
@@ -173,6 +184,24 @@ mod rpc_macro;
 ///   Aliases are processed ignoring the namespace, so add the complete name, including the namespace.
 /// - `blocking`: when set method execution will always spawn on a dedicated thread. Only usable with non-`async` methods.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `variant`: name of the variant in the generated request and response enums. Defaults to the Rust method
+///   name in `PascalCase`.
+/// - `timeout`: maximum duration of a call, written as `"500us"`, `"500ms"`, `"5s"`, `"1m"` or `"2h"`. A call that takes
+///   longer is answered with a `-32000 request timed out` error, or the one set with
+///   `<Trait>ServerBuilder::timeout_error`. Overrides the `timeout` of the `rpc` attribute.
+/// - `requires`: role the caller must have to call the method. It is listed in `RpcMethods::INFO` and
+///   enforced by `tower_json_rpc::auth::RpcAuthLayer`. Overrides the `requires` of the `rpc` attribute.
+/// - `rate_limit`: maximum rate of calls of each caller, written as requests per duration, such as `"10/s"` or
+///   `"100/5m"`. It is listed in `RpcMethods::INFO` and enforced by
+///   `tower_json_rpc::rate_limit::JsonRpcRateLimitLayer`. Overrides the `rate_limit` of the `rpc` attribute.
+/// - `cost`: compute units charged per call, either a number such as `cost = 75` or the path of a
+///   `fn(&Params) -> u64` computing them from the params. It is listed in `RpcMethods::INFO` and charged by
+///   `tower_json_rpc::cost::QuotaLayer`. Overrides the `cost` of the `rpc` attribute.
+/// - `cacheable`: the results of the method only depend on its params, so `tower_json_rpc::cache::RpcCacheLayer`
+///   can answer identical calls from its cache. Errors are never cached.
+/// - `ttl`: how long the results of a `cacheable` method are cached, such as `ttl = "30s"`. Defaults to the TTL
+///   of the cache layer.
+/// - `with_extensions`: allow arguments marked with `#[argument(extract)]`.
 ///
 /// **Method requirements:**
 ///
@@ -196,6 +225,7 @@ mod rpc_macro;
 /// - `unsubscribe_aliases` (optional): Similar to `aliases` but for `unsubscribe`.
 /// - `item` (mandatory): type of items yielded by the subscription. Note that it must be the type, not string.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `variant`: name of the variant in the generated request enum. Defaults to the Rust method name in `PascalCase`.
 ///
 /// **Method requirements:**
 ///
@@ -215,6 +245,9 @@ mod rpc_macro;
 /// **Arguments:**
 ///
 /// - `rename`: rename the generated JSON key.
+/// - `extract`: fill the argument from the request parts (id, extensions, HTTP headers) with
+///   `tower_json_rpc::extract::FromRequestParts` instead of from `params`. Extracted arguments are
+///   left out of the request enum and the client methods. Requires `with_extensions` on the method.
 ///
 ///
 /// ## Full workflow example
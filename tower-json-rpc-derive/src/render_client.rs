@@ -26,14 +26,20 @@
 
 use super::RpcDescription;
 use crate::attributes::ParamKind;
-use crate::rpc_macro::RpcSubscription;
+use crate::rpc_macro::{RpcFnArg, RpcSubscription};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
 impl RpcDescription {
     pub(super) fn render_client(&self) -> Result<TokenStream2, syn::Error> {
+        let krate = &self.crate_path;
+        let types = self.private_path("jsonrpsee_types");
+        let serde_json = self.private_path("serde_json");
+        let tower = self.private_path("tower");
+        let jsonrpsee = self.private_path("jsonrpsee");
         let trait_name = &self.trait_def.ident;
         let client_trait_name = quote::format_ident!("{}Client", trait_name);
+        let batch_trait_name = quote::format_ident!("{}Batch", trait_name);
         let request_enum_name = quote::format_ident!("{}Request", trait_name);
         let response_enum_name = quote::format_ident!("{}Response", trait_name);
 
@@ -49,10 +55,12 @@ impl RpcDescription {
         let server_request_impl =
             self.render_server_request_impl(&request_enum_name, &response_enum_name)?;
 
-        let methods = self.methods.iter().map(|method| {
+        let (methods, batch_methods): (Vec<_>, Vec<_>) = self.methods.iter().map(|method| {
 			let method_ident = &method.signature.sig.ident;
+			let docs = &method.docs;
+			let deprecated = method.deprecation.as_ref().map(|deprecation| &deprecation.attr);
 			let generics = &method.signature.sig.generics;
-			let inputs = &method.signature.sig.inputs;
+			let inputs = client_inputs(&method.signature.sig, &method.args);
 			let ok_ty = ok_type(&method.signature.sig.output);
 			let method_name = self.rpc_identifier(&method.name);
 			let param_idents: Vec<_> = method.params.iter().map(|param| &param.arg_pat.ident).collect();
@@ -62,62 +70,92 @@ impl RpcDescription {
 				let param_names = method.params.iter().map(|param| param.name());
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(::serde_json::value::to_raw_value(&{
-						let mut map = ::serde_json::Map::new();
-						#(map.insert(#param_names.to_string(), ::serde_json::to_value(#param_idents2).unwrap());)*
+					Some(#serde_json::value::to_raw_value(&{
+						let mut map = #serde_json::Map::new();
+						#(map.insert(#param_names.to_string(), #serde_json::to_value(#param_idents2).unwrap());)*
 						map
 					}).unwrap())
 				}
 			} else {
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(::serde_json::value::to_raw_value(&vec![
-						#(::serde_json::to_value(#param_idents2).unwrap()),*
+					Some(#serde_json::value::to_raw_value(&vec![
+						#(#serde_json::to_value(#param_idents2).unwrap()),*
 					]).unwrap())
 				}
 			};
 
-			quote! {
-				fn #method_ident #generics (#inputs) -> ::core::pin::Pin<
+			let output = quote! {
+				::core::pin::Pin<
 					Box<
 						dyn ::core::future::Future<
-							Output = Result<#ok_ty, ::tower_json_rpc::error::JsonRpcError>,
+							Output = Result<#ok_ty, #krate::error::JsonRpcError>,
 						> + 'static,
 					>,
-				> {
-					let request_id: ::jsonrpsee_types::Id<'static> = ::jsonrpsee_types::Id::Number(0);
-					let request: ::jsonrpsee_types::Request<'static> = ::jsonrpsee_types::Request::<'static>::owned(
-						#method_name.into(),
-						#params_value,
-						request_id,
-					);
+				>
+			};
+			let request = quote! {
+				let request: #types::Request<'static> = #types::Request::<'static>::owned(
+					#method_name.into(),
+					#params_value,
+					#types::Id::Number(0),
+				);
+			};
+			let decode = quote! {
+				match response.payload {
+					#types::ResponsePayload::Success(value) => {
+						let result: #ok_ty = #serde_json::from_value(value.into_owned())?;
+						Ok(result)
+					}
+					#types::ResponsePayload::Error(err) => {
+						Err(#krate::error::JsonRpcError::Rpc(err.into_owned()))
+					}
+				}
+			};
+
+			let client_method = quote! {
+				#docs
+				#deprecated
+				fn #method_ident #generics (#(#inputs),*) -> #output {
+					#request
+					let request_id = request.id.clone();
 					let service = self.clone();
 					Box::pin(async move {
 						let client_request = Req::from_json_rpc_request(request).await?;
 						let mut service = service;
-						::tower_json_rpc::__private::futures_util::future::poll_fn(|cx| {
-							match ::tower::Service::poll_ready(&mut service, cx) {
+						#krate::__private::futures_util::future::poll_fn(|cx| {
+							match #tower::Service::poll_ready(&mut service, cx) {
 								::core::task::Poll::Ready(Ok(())) => ::core::task::Poll::Ready(Ok(())),
 								::core::task::Poll::Ready(Err(err)) => ::core::task::Poll::Ready(Err(err.into())),
 								::core::task::Poll::Pending => ::core::task::Poll::Pending,
 							}
 						})
 						.await?;
-						let response = ::tower::Service::call(&mut service, client_request).await.map_err(Into::into)?;
-						let response = <Req::Response as ::tower_json_rpc::client::ClientResponse>::to_json_rpc_response(response).await?;
-						match response.payload {
-							::jsonrpsee_types::ResponsePayload::Success(value) => {
-								let result: #ok_ty = ::serde_json::from_value(value.into_owned())?;
-								Ok(result)
-							}
-							::jsonrpsee_types::ResponsePayload::Error(err) => {
-								Err(::tower_json_rpc::error::JsonRpcError::RequestProcessing(err.to_string()))
-							}
-						}
+						let response = #tower::Service::call(&mut service, client_request).await.map_err(Into::into)?;
+						let response = <Req::Response as #krate::client::ClientResponse>::to_json_rpc_response(response).await?;
+						let response = #krate::client::verify_id(&request_id, response)?;
+						#decode
 					})
 				}
-			}
-		});
+			};
+			let batch_method = quote! {
+				#docs
+				#deprecated
+				fn #method_ident #generics (#(#inputs),*) -> #output;
+			};
+			let batch_impl = quote! {
+				fn #method_ident #generics (#(#inputs),*) -> #output {
+					#request
+					let response = self.call(request);
+					Box::pin(async move {
+						let response = response.await?;
+						#decode
+					})
+				}
+			};
+			(client_method, (batch_method, batch_impl))
+		}).unzip();
+        let (batch_methods, batch_impls): (Vec<_>, Vec<_>) = batch_methods.into_iter().unzip();
 
         // Generate subscription methods if there are any
         let subscription_methods: Vec<_> = self.subscriptions.iter()
@@ -126,26 +164,39 @@ impl RpcDescription {
 
         // Generate the client trait - for regular RPC methods
         let client_trait = if !self.methods.is_empty() {
+            let doc = format!(" Client extension methods for the `{trait_name}` RPC API.");
+            let batch_doc = format!(" Methods of the `{trait_name}` RPC API queueing their call on a batch.");
             quote! {
+                #[doc = #doc]
                 pub trait #client_trait_name<Req>
                 where
-                    Req: ::tower_json_rpc::client::ClientRequest + Send + 'static,
-                    Req::Response: ::tower_json_rpc::client::ClientResponse + Send + 'static,
-                    Self: ::tower::Service<Req, Response = <Req as ::tower_json_rpc::client::ClientRequest>::Response> + Clone + Send + 'static,
-                    <Self as ::tower::Service<Req>>::Future: 'static,
-                    <Self as ::tower::Service<Req>>::Error: Into<::tower_json_rpc::error::JsonRpcError> + Send + 'static,
+                    Req: #krate::client::ClientRequest + Send + 'static,
+                    Req::Response: #krate::client::ClientResponse + Send + 'static,
+                    Self: #tower::Service<Req, Response = <Req as #krate::client::ClientRequest>::Response> + Clone + Send + 'static,
+                    <Self as #tower::Service<Req>>::Future: 'static,
+                    <Self as #tower::Service<Req>>::Error: Into<#krate::error::JsonRpcError> + Send + 'static,
                 {
                     #(#methods)*
                 }
 
                 impl<T, Req> #client_trait_name<Req> for T
                 where
-                    Req: ::tower_json_rpc::client::ClientRequest + Send + 'static,
-                    Req::Response: ::tower_json_rpc::client::ClientResponse + Send + 'static,
-                    T: ::tower::Service<Req, Response = <Req as ::tower_json_rpc::client::ClientRequest>::Response> + Clone + Send + 'static,
-                    <T as ::tower::Service<Req>>::Future: 'static,
-                    <T as ::tower::Service<Req>>::Error: Into<::tower_json_rpc::error::JsonRpcError> + Send + 'static,
+                    Req: #krate::client::ClientRequest + Send + 'static,
+                    Req::Response: #krate::client::ClientResponse + Send + 'static,
+                    T: #tower::Service<Req, Response = <Req as #krate::client::ClientRequest>::Response> + Clone + Send + 'static,
+                    <T as #tower::Service<Req>>::Future: 'static,
+                    <T as #tower::Service<Req>>::Error: Into<#krate::error::JsonRpcError> + Send + 'static,
                 {}
+
+                #[doc = #batch_doc]
+                pub trait #batch_trait_name {
+                    #(#batch_methods)*
+                }
+
+                #[allow(deprecated)]
+                impl<S> #batch_trait_name for #krate::client::Batch<S> {
+                    #(#batch_impls)*
+                }
             }
         } else {
             TokenStream2::new()
@@ -154,14 +205,16 @@ impl RpcDescription {
         // Generate subscription client trait - for subscription methods
         let subscription_client_trait = if !self.subscriptions.is_empty() {
             let subscription_client_trait_name = quote::format_ident!("{}SubscriptionClient", trait_name);
+            let doc = format!(" Client subscription methods for the `{trait_name}` RPC API.");
             quote! {
-                pub trait #subscription_client_trait_name: ::jsonrpsee::core::client::SubscriptionClientT {
+                #[doc = #doc]
+                pub trait #subscription_client_trait_name: #jsonrpsee::core::client::SubscriptionClientT {
                     #(#subscription_methods)*
                 }
 
                 impl<T> #subscription_client_trait_name for T
                 where
-                    T: ::jsonrpsee::core::client::SubscriptionClientT,
+                    T: #jsonrpsee::core::client::SubscriptionClientT,
                 {}
             }
         } else {
@@ -178,21 +231,24 @@ impl RpcDescription {
     }
 
     fn render_subscription_method(&self, sub: &RpcSubscription) -> Result<TokenStream2, syn::Error> {
+        let jsonrpsee = self.private_path("jsonrpsee");
         let method_ident = &sub.signature.sig.ident;
-        let inputs = &sub.signature.sig.inputs;
+        let docs = &sub.docs;
+        let deprecated = sub.deprecation.as_ref().map(|deprecation| &deprecation.attr);
+        let inputs = client_inputs(&sub.signature.sig, &sub.args);
         let item_ty = &sub.item;
         let subscribe_method = self.rpc_identifier(&sub.name);
         let unsubscribe_method = self.rpc_identifier(&sub.unsubscribe);
 
         let param_idents: Vec<_> = sub.params.iter().map(|param| &param.arg_pat.ident).collect();
         let params_builder = if sub.params.is_empty() {
-            quote! { ::jsonrpsee::core::params::ArrayParams::new() }
+            quote! { #jsonrpsee::core::params::ArrayParams::new() }
         } else if sub.param_kind == ParamKind::Map {
             let param_names = sub.params.iter().map(|p| p.name());
             let param_idents2 = param_idents.clone();
             quote! {
                 {
-                    let mut params = ::jsonrpsee::core::params::ObjectParams::new();
+                    let mut params = #jsonrpsee::core::params::ObjectParams::new();
                     #(params.insert(#param_names, #param_idents2).unwrap();)*
                     params
                 }
@@ -201,7 +257,7 @@ impl RpcDescription {
             let param_idents2 = param_idents.clone();
             quote! {
                 {
-                    let mut params = ::jsonrpsee::core::params::ArrayParams::new();
+                    let mut params = #jsonrpsee::core::params::ArrayParams::new();
                     #(params.insert(#param_idents2).unwrap();)*
                     params
                 }
@@ -209,14 +265,16 @@ impl RpcDescription {
         };
 
         Ok(quote! {
-            fn #method_ident(#inputs) -> impl ::core::future::Future<
+            #docs
+            #deprecated
+            fn #method_ident(#(#inputs),*) -> impl ::core::future::Future<
                 Output = Result<
-                    ::jsonrpsee::core::client::Subscription<#item_ty>,
-                    ::jsonrpsee::core::client::Error
+                    #jsonrpsee::core::client::Subscription<#item_ty>,
+                    #jsonrpsee::core::client::Error
                 >
             > + Send {
                 let params = #params_builder;
-                ::jsonrpsee::core::client::SubscriptionClientT::subscribe(
+                #jsonrpsee::core::client::SubscriptionClientT::subscribe(
                     self,
                     #subscribe_method,
                     params,
@@ -231,7 +289,9 @@ impl RpcDescription {
         enum_name: &syn::Ident,
     ) -> Result<TokenStream2, syn::Error> {
         let variants = self.methods.iter().map(|method| {
-            let variant_name = to_variant_name(&method.name);
+            let variant_name = &method.variant;
+            let docs = &method.docs;
+            let deprecated = method.deprecation.as_ref().map(|deprecation| &deprecation.attr);
             let params = method.params.iter().map(|param| {
                 let name = &param.arg_pat.ident;
                 let ty = &param.ty;
@@ -239,13 +299,17 @@ impl RpcDescription {
             });
 
             quote! {
+                #docs
+                #deprecated
                 #variant_name {
                     #(#params),*
                 }
             }
         });
+        let doc = format!(" Typed requests of the `{}` RPC API, one variant per method.", self.trait_def.ident);
 
         Ok(quote! {
+            #[doc = #doc]
             #[derive(Debug, Clone)]
             pub enum #enum_name {
                 #(#variants,)*
@@ -258,8 +322,11 @@ impl RpcDescription {
         request_enum_name: &syn::Ident,
         response_enum_name: &syn::Ident,
     ) -> Result<TokenStream2, syn::Error> {
+        let krate = &self.crate_path;
+        let types = self.private_path("jsonrpsee_types");
+        let serde_json = self.private_path("serde_json");
         let arms = self.methods.iter().map(|method| {
-			let variant_name = to_variant_name(&method.name);
+			let variant_name = &method.variant;
 			let method_name = self.rpc_identifier(&method.name);
 			let param_idents: Vec<_> = method.params.iter().map(|param| &param.arg_pat.ident).collect();
 
@@ -269,35 +336,36 @@ impl RpcDescription {
 				let param_names = method.params.iter().map(|p| p.name());
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(::serde_json::value::to_raw_value(&{
-						let mut map = ::serde_json::Map::new();
-						#(map.insert(#param_names.to_string(), ::serde_json::to_value(#param_idents2).unwrap());)*
+					Some(#serde_json::value::to_raw_value(&{
+						let mut map = #serde_json::Map::new();
+						#(map.insert(#param_names.to_string(), #serde_json::to_value(#param_idents2).unwrap());)*
 						map
 					}).unwrap())
 				}
 			} else {
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(::serde_json::value::to_raw_value(&vec![
-						#(::serde_json::to_value(#param_idents2).unwrap()),*
+					Some(#serde_json::value::to_raw_value(&vec![
+						#(#serde_json::to_value(#param_idents2).unwrap()),*
 					]).unwrap())
 				}
 			};
 
 			quote! {
 				#request_enum_name::#variant_name { #(#param_idents),* } => {
-					::jsonrpsee_types::Request::owned(#method_name.into(), #params_value, ::jsonrpsee_types::Id::Number(0))
+					#types::Request::owned(#method_name.into(), #params_value, #types::Id::Number(0))
 				}
 			}
 		});
 
         Ok(quote! {
-            impl ::tower_json_rpc::server::ServerRequest for #request_enum_name {
+            #[allow(deprecated)]
+            impl #krate::server::ServerRequest for #request_enum_name {
                 type Response = #response_enum_name;
 
                 fn into_json_rpc_request(
                     self,
-                ) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<::jsonrpsee_types::Request<'static>, ::tower_json_rpc::error::JsonRpcError>> + Send + 'static>> {
+                ) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<#types::Request<'static>, #krate::error::JsonRpcError>> + Send + 'static>> {
                     Box::pin(async move {
                         Ok(match self {
                             #(#arms)*
@@ -312,76 +380,114 @@ impl RpcDescription {
         &self,
         enum_name: &syn::Ident,
     ) -> Result<TokenStream2, syn::Error> {
+        let krate = &self.crate_path;
+        let types = self.private_path("jsonrpsee_types");
+        let serde_json = self.private_path("serde_json");
         let variants = self.methods.iter().map(|method| {
-            let variant_name = to_variant_name(&method.name);
+            let variant_name = &method.variant;
+            let docs = &method.docs;
+            let deprecated = method.deprecation.as_ref().map(|deprecation| &deprecation.attr);
             let ok_ty = ok_type(&method.signature.sig.output);
             quote! {
+                #docs
+                #deprecated
                 #variant_name(#ok_ty)
             }
         });
+        let doc = format!(" Typed responses of the `{}` RPC API, one variant per method.", self.trait_def.ident);
 
         // Generate try-parse arms for from_json_rpc_response
         // Note: This tries each variant type in order and returns the first successful parse.
-        // This may not correctly identify the variant if multiple methods return the same type.
+        // This may not correctly identify the variant if multiple methods return the same type,
+        // which is why RpcCall decodes with from_method_response instead.
         let try_parse_arms = self.methods.iter().map(|method| {
-            let variant_name = to_variant_name(&method.name);
+            let variant_name = &method.variant;
             let ok_ty = ok_type(&method.signature.sig.output);
             quote! {
-                if let Ok(result) = ::serde_json::from_value::<#ok_ty>(value.clone()) {
+                if let Ok(result) = #serde_json::from_value::<#ok_ty>(value.clone()) {
                     return Ok(#enum_name::#variant_name(result));
                 }
             }
         });
 
+        let method_arms = self.methods.iter().map(|method| {
+            let variant_name = &method.variant;
+            let method_name = self.rpc_identifier(&method.name);
+            let ok_ty = ok_type(&method.signature.sig.output);
+            quote! {
+                #method_name => #serde_json::from_value::<#ok_ty>(value)
+                    .map(#enum_name::#variant_name)
+                    .map_err(Into::into),
+            }
+        });
+
         Ok(quote! {
+            #[doc = #doc]
             #[derive(Debug, Clone)]
             pub enum #enum_name {
                 #(#variants,)*
             }
 
-            impl ::tower_json_rpc::server::ServerResponse for #enum_name {
+            #[allow(deprecated)]
+            impl #krate::server::ServerResponse for #enum_name {
                 fn from_json_rpc_response(
-                    response: ::jsonrpsee_types::Response<'static, ::serde_json::Value>,
-                ) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<Self, ::tower_json_rpc::error::JsonRpcError>> + Send + 'static>> {
+                    response: #types::Response<'static, #serde_json::Value>,
+                ) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<Self, #krate::error::JsonRpcError>> + Send + 'static>> {
                     Box::pin(async move {
                         match response.payload {
-                            ::jsonrpsee_types::ResponsePayload::Success(value) => {
+                            #types::ResponsePayload::Success(value) => {
                                 let value = value.into_owned();
                                 #(#try_parse_arms)*
-                                Err(::tower_json_rpc::error::JsonRpcError::RequestProcessing(
+                                Err(#krate::error::JsonRpcError::RequestProcessing(
                                     "Failed to deserialize response into any known variant".to_string()
                                 ))
                             }
-                            ::jsonrpsee_types::ResponsePayload::Error(err) => {
-                                Err(::tower_json_rpc::error::JsonRpcError::RequestProcessing(err.to_string()))
+                            #types::ResponsePayload::Error(err) => {
+                                Err(#krate::error::JsonRpcError::Rpc(err.into_owned()))
                             }
                         }
                     })
                 }
+
+                fn from_method_response(
+                    method: &str,
+                    response: #types::Response<'static, #serde_json::Value>,
+                ) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = Result<Self, #krate::error::JsonRpcError>> + Send + 'static>> {
+                    let result = match response.payload {
+                        #types::ResponsePayload::Success(value) => {
+                            let value = value.into_owned();
+                            match method {
+                                #(#method_arms)*
+                                _ => Err(#krate::error::JsonRpcError::RequestProcessing(
+                                    format!("No response variant for method `{method}`")
+                                )),
+                            }
+                        }
+                        #types::ResponsePayload::Error(err) => {
+                            Err(#krate::error::JsonRpcError::Rpc(err.into_owned()))
+                        }
+                    };
+                    Box::pin(async move { result })
+                }
             }
         })
     }
 }
 
-fn to_variant_name(method_name: &str) -> syn::Ident {
-    let mut result = String::new();
-    let mut capitalize_next = true;
-
-    for ch in method_name.chars() {
-        if ch == '_' || ch == '-' {
-            capitalize_next = true;
-        } else if capitalize_next {
-            result.push(ch.to_ascii_uppercase());
-            capitalize_next = false;
-        } else {
-            result.push(ch);
-        }
-    }
-
-    syn::Ident::new(&result, proc_macro2::Span::call_site())
+/// The inputs of a client method: the receiver and the arguments sent as `params`.
+fn client_inputs<'a>(sig: &'a syn::Signature, args: &[RpcFnArg]) -> Vec<&'a syn::FnArg> {
+    sig.inputs
+        .iter()
+        .filter(|input| match input {
+            syn::FnArg::Receiver(_) => true,
+            syn::FnArg::Typed(arg) => !args.iter().any(|rpc_arg| {
+                rpc_arg.extract && matches!(&*arg.pat, syn::Pat::Ident(pat) if pat.ident == rpc_arg.arg_pat.ident)
+            }),
+        })
+        .collect()
 }
 
-fn ok_type(output: &syn::ReturnType) -> syn::Type {
+pub(crate) fn ok_type(output: &syn::ReturnType) -> syn::Type {
     match output {
         syn::ReturnType::Default => syn::parse_quote!(()),
         syn::ReturnType::Type(_, ty) => {
@@ -0,0 +1,311 @@
+use super::RpcDescription;
+use crate::attributes::ParamKind;
+use crate::render_server::names_with_aliases;
+use crate::rpc_macro::RpcFnArg;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+impl RpcDescription {
+	/// Renders `Serialize` and `Deserialize` for the request enum and, with the client, the response enum.
+	///
+	/// Requests use the wire shape `{ "method": ..., "params": ... }` and responses use
+	/// `{ "method": ..., "result": ... }`, so that a typed value can be stored and replayed.
+	pub(super) fn render_serde(&self) -> Result<TokenStream2, syn::Error> {
+		let request_enum_name = quote::format_ident!("{}Request", self.trait_def.ident);
+		let response_enum_name = quote::format_ident!("{}Response", self.trait_def.ident);
+
+		// The server renders `TryFrom<Request>` already, the client-only enum needs its own.
+		let try_from_request_impl = if self.needs_server {
+			TokenStream2::new()
+		} else {
+			self.render_try_from_request(&request_enum_name, false)?
+		};
+		let request_serde = self.render_request_serde(&request_enum_name);
+		let response_serde =
+			if self.needs_client { self.render_response_serde(&response_enum_name) } else { TokenStream2::new() };
+
+		Ok(quote! {
+			#try_from_request_impl
+			#request_serde
+			#response_serde
+		})
+	}
+
+	fn render_request_serde(&self, enum_name: &syn::Ident) -> TokenStream2 {
+		let types = self.private_path("jsonrpsee_types");
+		let serde = self.private_path("serde");
+		let serde_json = self.private_path("serde_json");
+
+		let methods = self.methods.iter().map(|method| {
+			(&method.variant, self.rpc_identifier(&method.name), &method.aliases, &method.params, &method.param_kind)
+		});
+		let subscriptions = self
+			.subscriptions
+			.iter()
+			.filter(|_| self.needs_server)
+			.map(|sub| (&sub.variant, self.rpc_identifier(&sub.name), &sub.aliases, &sub.params, &sub.param_kind));
+		let calls: Vec<_> = methods.chain(subscriptions).collect();
+		let enum_str = enum_name.to_string();
+		let expecting = format!("a `{enum_str}` with its method and params");
+
+		let serialize_arms = calls.iter().map(|(variant_name, method_name, _, params, param_kind)| {
+			let param_idents: Vec<_> = params.iter().map(|param| &param.arg_pat.ident).collect();
+			let params_field = serialize_params(&serde, params, param_kind);
+			let len = 1 + !params.is_empty() as usize;
+
+			quote! {
+				#enum_name::#variant_name { #(#param_idents),* } => {
+					let mut state = serializer.serialize_struct(#enum_str, #len)?;
+					state.serialize_field("method", #method_name)?;
+					#params_field
+					state.end()
+				}
+			}
+		});
+
+		// Formats serializing structs as sequences have the params in the order of the method.
+		let seq_arms = calls.iter().map(|(variant_name, method_name, aliases, params, _)| {
+			let method_names = names_with_aliases(method_name.clone(), aliases);
+			let param_idents: Vec<_> = params.iter().map(|param| &param.arg_pat.ident).collect();
+			let read_params = (!params.is_empty()).then(|| {
+				quote! {
+					let (#(#param_idents,)*) = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(1, &self))?;
+				}
+			});
+			quote! {
+				#(#method_names)|* => {
+					#read_params
+					Ok(#enum_name::#variant_name { #(#param_idents),* })
+				}
+			}
+		});
+
+
+		quote! {
+			#[allow(deprecated)]
+			impl #serde::Serialize for #enum_name {
+				fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+				where
+					S: #serde::Serializer,
+				{
+					use #serde::ser::SerializeStruct as _;
+
+					match self {
+						#(#serialize_arms)*
+					}
+				}
+			}
+
+			#[allow(deprecated)]
+			impl<'de> #serde::Deserialize<'de> for #enum_name {
+				fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+				where
+					D: #serde::Deserializer<'de>,
+				{
+					use #serde::de::Error as _;
+
+					struct Visitor;
+
+					impl<'de> #serde::de::Visitor<'de> for Visitor {
+						type Value = #enum_name;
+
+						fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+							f.write_str(#expecting)
+						}
+
+						fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+						where
+							A: #serde::de::SeqAccess<'de>,
+						{
+							let method: String = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+							match method.as_str() {
+								#(#seq_arms,)*
+								_ => Err(A::Error::custom(format!("Unknown method: {method}"))),
+							}
+						}
+
+						// Self-describing formats may put the params first, so they are buffered and
+						// parsed like the params of a JSON-RPC request.
+						fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+						where
+							A: #serde::de::MapAccess<'de>,
+						{
+							let mut method = None;
+							let mut params = None;
+							while let Some(key) = map.next_key::<String>()? {
+								match key.as_str() {
+									"method" => method = Some(map.next_value::<String>()?),
+									"params" => params = map.next_value::<Option<#serde_json::Value>>()?,
+									_ => {
+										map.next_value::<#serde::de::IgnoredAny>()?;
+									}
+								}
+							}
+							let method = method.ok_or_else(|| A::Error::missing_field("method"))?;
+							let params = params
+								.map(|params| #serde_json::value::to_raw_value(&params))
+								.transpose()
+								.map_err(A::Error::custom)?;
+
+							let request = #types::Request::owned(method, params, #types::Id::Null);
+							#enum_name::try_from(request).map_err(|err| A::Error::custom(err.to_string()))
+						}
+					}
+
+					deserializer.deserialize_struct(#enum_str, &["method", "params"], Visitor)
+				}
+			}
+		}
+	}
+
+	fn render_response_serde(&self, enum_name: &syn::Ident) -> TokenStream2 {
+		let serde = self.private_path("serde");
+		let serde_json = self.private_path("serde_json");
+
+		let serialize_arms = self.methods.iter().map(|method| {
+			let variant_name = &method.variant;
+			let method_name = self.rpc_identifier(&method.name);
+			quote! {
+				#enum_name::#variant_name(result) => {
+					state.serialize_field("method", #method_name)?;
+					state.serialize_field("result", result)?;
+				}
+			}
+		});
+
+		let seq_arms = self.methods.iter().map(|method| {
+			let variant_name = &method.variant;
+			let method_names = names_with_aliases(self.rpc_identifier(&method.name), &method.aliases);
+			quote! {
+				#(#method_names)|* => seq
+					.next_element()?
+					.map(#enum_name::#variant_name)
+					.ok_or_else(|| A::Error::invalid_length(1, &self))
+			}
+		});
+
+		let map_arms = self.methods.iter().map(|method| {
+			let variant_name = &method.variant;
+			let method_names = names_with_aliases(self.rpc_identifier(&method.name), &method.aliases);
+			quote! {
+				#(#method_names)|* => #serde_json::from_value(result).map(#enum_name::#variant_name)
+			}
+		});
+
+		let enum_str = enum_name.to_string();
+		let expecting = format!("a `{enum_str}` with its method and result");
+
+		quote! {
+			#[allow(deprecated)]
+			impl #serde::Serialize for #enum_name {
+				fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+				where
+					S: #serde::Serializer,
+				{
+					use #serde::ser::SerializeStruct as _;
+
+					let mut state = serializer.serialize_struct(#enum_str, 2)?;
+					match self {
+						#(#serialize_arms)*
+					}
+					state.end()
+				}
+			}
+
+			#[allow(deprecated)]
+			impl<'de> #serde::Deserialize<'de> for #enum_name {
+				fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+				where
+					D: #serde::Deserializer<'de>,
+				{
+					use #serde::de::Error as _;
+
+					struct Visitor;
+
+					impl<'de> #serde::de::Visitor<'de> for Visitor {
+						type Value = #enum_name;
+
+						fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+							f.write_str(#expecting)
+						}
+
+						fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+						where
+							A: #serde::de::SeqAccess<'de>,
+						{
+							let method: String = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+							match method.as_str() {
+								#(#seq_arms,)*
+								_ => Err(A::Error::custom(format!("Unknown method: {method}"))),
+							}
+						}
+
+						// The result is buffered, in case it comes before the method.
+						fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+						where
+							A: #serde::de::MapAccess<'de>,
+						{
+							let mut method = None;
+							let mut result = None;
+							while let Some(key) = map.next_key::<String>()? {
+								match key.as_str() {
+									"method" => method = Some(map.next_value::<String>()?),
+									"result" => result = Some(map.next_value::<#serde_json::Value>()?),
+									_ => {
+										map.next_value::<#serde::de::IgnoredAny>()?;
+									}
+								}
+							}
+							let method = method.ok_or_else(|| A::Error::missing_field("method"))?;
+							let result = result.ok_or_else(|| A::Error::missing_field("result"))?;
+
+							match method.as_str() {
+								#(#map_arms,)*
+								_ => return Err(A::Error::custom(format!("Unknown method: {method}"))),
+							}
+							.map_err(A::Error::custom)
+						}
+					}
+
+					deserializer.deserialize_struct(#enum_str, &["method", "result"], Visitor)
+				}
+			}
+		}
+	}
+}
+
+/// Renders the `params` field of a request, as a tuple or, with `param_kind = map`, as a struct
+/// keyed by the param names. Methods without params have no `params` field.
+fn serialize_params(serde: &TokenStream2, params: &[RpcFnArg], param_kind: &ParamKind) -> TokenStream2 {
+	if params.is_empty() {
+		return TokenStream2::new();
+	}
+
+	let param_idents: Vec<_> = params.iter().map(|param| &param.arg_pat.ident).collect();
+	if *param_kind != ParamKind::Map {
+		return quote! { state.serialize_field("params", &(#(#param_idents,)*))?; };
+	}
+
+	let names = params.iter().map(|param| param.name());
+	let tys = params.iter().map(|param| &param.ty);
+	let indices = (0..params.len()).map(syn::Index::from);
+	let len = params.len();
+	quote! {
+		struct Params<'a>(#(&'a #tys,)*);
+
+		impl #serde::Serialize for Params<'_> {
+			fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+			where
+				S: #serde::Serializer,
+			{
+				use #serde::ser::SerializeStruct as _;
+
+				let mut state = serializer.serialize_struct("params", #len)?;
+				#(state.serialize_field(#names, self.#indices)?;)*
+				state.end()
+			}
+		}
+
+		state.serialize_field("params", &Params(#(#param_idents,)*))?;
+	}
+}
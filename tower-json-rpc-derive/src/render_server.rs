@@ -25,9 +25,14 @@
 // DEALINGS IN THE SOFTWARE.
 
 use super::RpcDescription;
+use crate::attributes::Cost;
+use crate::render_client::ok_type;
+use crate::rpc_macro::RpcMethod;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use std::borrow::Cow;
+use std::time::Duration;
+use syn::ext::IdentExt;
 
 impl RpcDescription {
 	pub(super) fn render_server(&self) -> Result<TokenStream2, syn::Error> {
@@ -35,23 +40,140 @@ impl RpcDescription {
 		let request_enum_name = quote::format_ident!("{}Request", trait_name);
 		let server_layer_name = quote::format_ident!("{}ServerLayer", trait_name);
 		let server_service_name = quote::format_ident!("{}ServerService", trait_name);
+		let server_builder_name = quote::format_ident!("{}ServerBuilder", trait_name);
+		let method_stacks_name = quote::format_ident!("{}MethodStacks", trait_name);
+		let server_config_name = quote::format_ident!("{}ServerConfig", trait_name);
 		
 		let request_enum = self.render_request_enum(&request_enum_name)?;
 		let into_request_impl = self.render_into_request(&request_enum_name)?;
-		let try_from_request_impl = self.render_try_from_request(&request_enum_name)?;
-		let server_layer = self.render_server_layer(&server_layer_name, &server_service_name, &request_enum_name)?;
+		let try_from_request_impl = self.render_try_from_request(&request_enum_name, true)?;
+		let server_layer = self.render_server_layer(&server_layer_name, &server_service_name, &server_config_name, &request_enum_name)?;
+		let server_builder = self.render_server_builder(&server_layer_name, &server_builder_name, &server_config_name, &method_stacks_name)?;
+		let dyn_trait = self.render_dyn_trait()?;
 		
 		Ok(quote! {
 			#request_enum
 			#into_request_impl
 			#try_from_request_impl
 			#server_layer
+			#server_builder
+			#dyn_trait
+		})
+	}
+	
+	/// Renders `Dyn<Trait>`, a dyn-compatible companion of the trait where `async` methods return
+	/// boxed futures, along with impls of the trait for `Arc<T>` and `Box<T>` of any `Dyn<Trait>`.
+	/// This lets the server layer take an `Arc<dyn Dyn<Trait>>` handler chosen at runtime.
+	///
+	/// The companion methods are prefixed with `dyn_`, as `Dyn<Trait>` is implemented for every
+	/// implementation of the trait and the same names would make method calls ambiguous.
+	fn render_dyn_trait(&self) -> Result<TokenStream2, syn::Error> {
+		let trait_name = &self.trait_def.ident;
+		let vis = &self.trait_def.vis;
+		let dyn_trait_name = quote::format_ident!("Dyn{}", trait_name);
+		
+		let signatures = self
+			.methods
+			.iter()
+			.map(|method| (&method.signature.sig, &method.docs))
+			.chain(self.subscriptions.iter().map(|sub| (&sub.signature.sig, &sub.docs)));
+		
+		let mut dyn_methods = Vec::new();
+		let mut to_trait_methods = Vec::new();
+		let mut to_dyn_methods = Vec::new();
+		for (sig, docs) in signatures {
+			let ident = &sig.ident;
+			let dyn_ident = quote::format_ident!("dyn_{}", ident);
+			let inputs = &sig.inputs;
+			let args = sig
+				.inputs
+				.iter()
+				.filter_map(|input| match input {
+					syn::FnArg::Typed(syn::PatType { pat, .. }) => match &**pat {
+						syn::Pat::Ident(pat) => Some(Ok(&pat.ident)),
+						pat => Some(Err(syn::Error::new_spanned(pat, "Method argument names must be valid Rust identifiers"))),
+					},
+					syn::FnArg::Receiver(_) => None,
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			
+			if sig.asyncness.is_some() {
+				let output = match &sig.output {
+					syn::ReturnType::Default => quote! { () },
+					syn::ReturnType::Type(_, ty) => quote! { #ty },
+				};
+				dyn_methods.push(quote! {
+					#docs
+					fn #dyn_ident(#inputs) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = #output> + Send + '_>>;
+				});
+				to_trait_methods.push(quote! {
+					fn #dyn_ident(#inputs) -> ::core::pin::Pin<Box<dyn ::core::future::Future<Output = #output> + Send + '_>> {
+						Box::pin(#trait_name::#ident(self, #(#args),*))
+					}
+				});
+				to_dyn_methods.push(quote! {
+					fn #ident(#inputs) -> impl ::core::future::Future<Output = #output> + Send {
+						#dyn_trait_name::#dyn_ident(&**self, #(#args),*)
+					}
+				});
+			} else {
+				let output = &sig.output;
+				dyn_methods.push(quote! {
+					#docs
+					fn #dyn_ident(#inputs) #output;
+				});
+				to_trait_methods.push(quote! {
+					fn #dyn_ident(#inputs) #output {
+						#trait_name::#ident(self, #(#args),*)
+					}
+				});
+				to_dyn_methods.push(quote! {
+					fn #ident(#inputs) #output {
+						#dyn_trait_name::#dyn_ident(&**self, #(#args),*)
+					}
+				});
+			}
+		}
+		
+		let doc = format!(" Dyn-compatible version of [`{trait_name}`], implemented for every `{trait_name}` implementation.");
+		
+		Ok(quote! {
+			#[doc = #doc]
+			#vis trait #dyn_trait_name: Send + Sync {
+				#(#dyn_methods)*
+			}
+			
+			#[allow(deprecated)]
+			impl<T> #dyn_trait_name for T
+			where
+				T: #trait_name + Send + Sync,
+			{
+				#(#to_trait_methods)*
+			}
+			
+			#[allow(deprecated)]
+			impl<T> #trait_name for ::std::sync::Arc<T>
+			where
+				T: #dyn_trait_name + ?Sized,
+			{
+				#(#to_dyn_methods)*
+			}
+			
+			#[allow(deprecated)]
+			impl<T> #trait_name for Box<T>
+			where
+				T: #dyn_trait_name + ?Sized,
+			{
+				#(#to_dyn_methods)*
+			}
 		})
 	}
 	
 	fn render_request_enum(&self, enum_name: &syn::Ident) -> Result<TokenStream2, syn::Error> {
 		let variants = self.methods.iter().map(|method| {
-			let variant_name = to_variant_name(&method.name);
+			let variant_name = &method.variant;
+			let docs = &method.docs;
+			let deprecated = method.deprecation.as_ref().map(|deprecation| &deprecation.attr);
 			let params = method.params.iter().map(|param| {
 				let name = &param.arg_pat.ident;
 				let ty = &param.ty;
@@ -59,6 +181,8 @@ impl RpcDescription {
 			});
 			
 			quote! {
+				#docs
+				#deprecated
 				#variant_name {
 					#(#params),*
 				}
@@ -66,7 +190,9 @@ impl RpcDescription {
 		});
 		
 		let sub_variants = self.subscriptions.iter().map(|sub| {
-			let variant_name = to_variant_name(&sub.name);
+			let variant_name = &sub.variant;
+			let docs = &sub.docs;
+			let deprecated = sub.deprecation.as_ref().map(|deprecation| &deprecation.attr);
 			let params = sub.params.iter().map(|param| {
 				let name = &param.arg_pat.ident;
 				let ty = &param.ty;
@@ -74,13 +200,18 @@ impl RpcDescription {
 			});
 			
 			quote! {
+				#docs
+				#deprecated
 				#variant_name {
 					#(#params),*
 				}
 			}
 		});
 		
+		let doc = format!(" Typed requests of the `{}` RPC API, one variant per method and subscription.", self.trait_def.ident);
+
 		Ok(quote! {
+			#[doc = #doc]
 			#[derive(Debug, Clone)]
 			pub enum #enum_name {
 				#(#variants,)*
@@ -90,8 +221,10 @@ impl RpcDescription {
 	}
 	
 	fn render_into_request(&self, enum_name: &syn::Ident) -> Result<TokenStream2, syn::Error> {
+		let types = self.private_path("jsonrpsee_types");
+		let serde_json = self.private_path("serde_json");
 		let arms = self.methods.iter().map(|method| {
-			let variant_name = to_variant_name(&method.name);
+			let variant_name = &method.variant;
 			let method_name = self.rpc_identifier(&method.name);
 			let param_idents: Vec<_> = method.params.iter().map(|param| &param.arg_pat.ident).collect();
 			
@@ -101,30 +234,30 @@ impl RpcDescription {
 				let param_names = method.params.iter().map(|p| p.name());
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(serde_json::value::to_raw_value(&{
-						let mut map = serde_json::Map::new();
-						#(map.insert(#param_names.to_string(), serde_json::to_value(#param_idents2).unwrap());)*
+					Some(#serde_json::value::to_raw_value(&{
+						let mut map = #serde_json::Map::new();
+						#(map.insert(#param_names.to_string(), #serde_json::to_value(#param_idents2).unwrap());)*
 						map
 					}).unwrap())
 				}
 			} else {
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(serde_json::value::to_raw_value(&vec![
-						#(serde_json::to_value(#param_idents2).unwrap()),*
+					Some(#serde_json::value::to_raw_value(&vec![
+						#(#serde_json::to_value(#param_idents2).unwrap()),*
 					]).unwrap())
 				}
 			};
 			
 			quote! {
 				#enum_name::#variant_name { #(#param_idents),* } => {
-					jsonrpsee_types::Request::owned(#method_name.into(), #params_value, jsonrpsee_types::Id::Number(0))
+					#types::Request::owned(#method_name.into(), #params_value, #types::Id::Number(0))
 				}
 			}
 		});
 		
 		let sub_arms = self.subscriptions.iter().map(|sub| {
-			let variant_name = to_variant_name(&sub.name);
+			let variant_name = &sub.variant;
 			let method_name = self.rpc_identifier(&sub.name);
 			let param_idents: Vec<_> = sub.params.iter().map(|param| &param.arg_pat.ident).collect();
 			
@@ -134,30 +267,31 @@ impl RpcDescription {
 				let param_names = sub.params.iter().map(|p| p.name());
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(serde_json::value::to_raw_value(&{
-						let mut map = serde_json::Map::new();
-						#(map.insert(#param_names.to_string(), serde_json::to_value(#param_idents2).unwrap());)*
+					Some(#serde_json::value::to_raw_value(&{
+						let mut map = #serde_json::Map::new();
+						#(map.insert(#param_names.to_string(), #serde_json::to_value(#param_idents2).unwrap());)*
 						map
 					}).unwrap())
 				}
 			} else {
 				let param_idents2 = param_idents.clone();
 				quote! {
-					Some(serde_json::value::to_raw_value(&vec![
-						#(serde_json::to_value(#param_idents2).unwrap()),*
+					Some(#serde_json::value::to_raw_value(&vec![
+						#(#serde_json::to_value(#param_idents2).unwrap()),*
 					]).unwrap())
 				}
 			};
 			
 			quote! {
 				#enum_name::#variant_name { #(#param_idents),* } => {
-					jsonrpsee_types::Request::owned(#method_name.into(), #params_value, jsonrpsee_types::Id::Number(0))
+					#types::Request::owned(#method_name.into(), #params_value, #types::Id::Number(0))
 				}
 			}
 		});
 		
 		Ok(quote! {
-			impl<'a> From<#enum_name> for jsonrpsee_types::Request<'a> {
+			#[allow(deprecated)]
+			impl<'a> From<#enum_name> for #types::Request<'a> {
 				fn from(req: #enum_name) -> Self {
 					match req {
 						#(#arms)*
@@ -168,9 +302,13 @@ impl RpcDescription {
 		})
 	}
 	
-	fn render_try_from_request(&self, enum_name: &syn::Ident) -> Result<TokenStream2, syn::Error> {
+	/// Renders `TryFrom<Request>` for the request enum. Subscription arms are only rendered when the
+	/// enum has subscription variants, which is not the case of the client-only enum.
+	pub(crate) fn render_try_from_request(&self, enum_name: &syn::Ident, with_subscriptions: bool) -> Result<TokenStream2, syn::Error> {
+		let types = self.private_path("jsonrpsee_types");
+		let serde_json = self.private_path("serde_json");
 		let method_arms = self.methods.iter().map(|method| {
-			let variant_name = to_variant_name(&method.name);
+			let variant_name = &method.variant;
 			let method_names = names_with_aliases(self.rpc_identifier(&method.name), &method.aliases);
 			let method_match = method_names.iter().map(|name| quote! { #name });
 			
@@ -182,17 +320,17 @@ impl RpcDescription {
 					let param_name = param.name();
 					let ty = &param.ty;
 					quote! {
-						let #name: #ty = map.get(#param_name)
-							.ok_or_else(|| jsonrpsee_types::ErrorObjectOwned::owned(
-								jsonrpsee_types::ErrorCode::InvalidParams.code(),
-								jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						let #name = map.get(#param_name)
+							.ok_or_else(|| #types::ErrorObjectOwned::owned(
+								#types::ErrorCode::InvalidParams.code(),
+								#types::ErrorCode::InvalidParams.message(),
 								Some(format!("Missing parameter: {}", #param_name)),
 							))?
 							.clone();
-						let #name: #ty = serde_json::from_value(#name)
-							.map_err(|err| jsonrpsee_types::ErrorObjectOwned::owned(
-								jsonrpsee_types::ErrorCode::InvalidParams.code(),
-								jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						let #name: #ty = #serde_json::from_value(#name)
+							.map_err(|err| #types::ErrorObjectOwned::owned(
+								#types::ErrorCode::InvalidParams.code(),
+								#types::ErrorCode::InvalidParams.message(),
 								Some(err.to_string()),
 							))?;
 					}
@@ -200,7 +338,7 @@ impl RpcDescription {
 				
 				let param_idents = method.params.iter().map(|p| &p.arg_pat.ident);
 				quote! {{
-					let map: serde_json::Map<String, serde_json::Value> = request.params().parse()?;
+					let map: #serde_json::Map<String, #serde_json::Value> = request.params().parse()?;
 					#(#param_extractions)*
 					Ok(Self::#variant_name { #(#param_idents),* })
 				}}
@@ -210,10 +348,10 @@ impl RpcDescription {
 					let name = &param.arg_pat.ident;
 					let ty = &param.ty;
 					quote! {
-						let #name: #ty = serde_json::from_value(arr[#i].clone())
-							.map_err(|err| jsonrpsee_types::ErrorObjectOwned::owned(
-								jsonrpsee_types::ErrorCode::InvalidParams.code(),
-								jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						let #name: #ty = #serde_json::from_value(arr[#i].clone())
+							.map_err(|err| #types::ErrorObjectOwned::owned(
+								#types::ErrorCode::InvalidParams.code(),
+								#types::ErrorCode::InvalidParams.message(),
 								Some(err.to_string()),
 							))?;
 					}
@@ -221,11 +359,11 @@ impl RpcDescription {
 				
 				let param_idents = method.params.iter().map(|p| &p.arg_pat.ident);
 				quote! {{
-					let arr: Vec<serde_json::Value> = request.params().parse()?;
+					let arr: Vec<#serde_json::Value> = request.params().parse()?;
 					if arr.len() != #param_count {
-						return Err(jsonrpsee_types::ErrorObjectOwned::owned(
-							jsonrpsee_types::ErrorCode::InvalidParams.code(),
-							jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						return Err(#types::ErrorObjectOwned::owned(
+							#types::ErrorCode::InvalidParams.code(),
+							#types::ErrorCode::InvalidParams.message(),
 							Some(format!("Expected {} parameters, got {}", #param_count, arr.len())),
 						));
 					}
@@ -239,8 +377,9 @@ impl RpcDescription {
 			}
 		});
 		
-		let sub_arms = self.subscriptions.iter().map(|sub| {
-			let variant_name = to_variant_name(&sub.name);
+		let subscriptions = if with_subscriptions { &self.subscriptions[..] } else { &[] };
+		let sub_arms = subscriptions.iter().map(|sub| {
+			let variant_name = &sub.variant;
 			let sub_names = names_with_aliases(self.rpc_identifier(&sub.name), &sub.aliases);
 			let sub_match = sub_names.iter().map(|name| quote! { #name });
 			
@@ -252,17 +391,17 @@ impl RpcDescription {
 					let param_name = param.name();
 					let ty = &param.ty;
 					quote! {
-						let #name: #ty = map.get(#param_name)
-							.ok_or_else(|| jsonrpsee_types::ErrorObjectOwned::owned(
-								jsonrpsee_types::ErrorCode::InvalidParams.code(),
-								jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						let #name = map.get(#param_name)
+							.ok_or_else(|| #types::ErrorObjectOwned::owned(
+								#types::ErrorCode::InvalidParams.code(),
+								#types::ErrorCode::InvalidParams.message(),
 								Some(format!("Missing parameter: {}", #param_name)),
 							))?
 							.clone();
-						let #name: #ty = serde_json::from_value(#name)
-							.map_err(|err| jsonrpsee_types::ErrorObjectOwned::owned(
-								jsonrpsee_types::ErrorCode::InvalidParams.code(),
-								jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						let #name: #ty = #serde_json::from_value(#name)
+							.map_err(|err| #types::ErrorObjectOwned::owned(
+								#types::ErrorCode::InvalidParams.code(),
+								#types::ErrorCode::InvalidParams.message(),
 								Some(err.to_string()),
 							))?;
 					}
@@ -270,7 +409,7 @@ impl RpcDescription {
 				
 				let param_idents = sub.params.iter().map(|p| &p.arg_pat.ident);
 				quote! {{
-					let map: serde_json::Map<String, serde_json::Value> = request.params().parse()?;
+					let map: #serde_json::Map<String, #serde_json::Value> = request.params().parse()?;
 					#(#param_extractions)*
 					Ok(Self::#variant_name { #(#param_idents),* })
 				}}
@@ -280,10 +419,10 @@ impl RpcDescription {
 					let name = &param.arg_pat.ident;
 					let ty = &param.ty;
 					quote! {
-						let #name: #ty = serde_json::from_value(arr[#i].clone())
-							.map_err(|err| jsonrpsee_types::ErrorObjectOwned::owned(
-								jsonrpsee_types::ErrorCode::InvalidParams.code(),
-								jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						let #name: #ty = #serde_json::from_value(arr[#i].clone())
+							.map_err(|err| #types::ErrorObjectOwned::owned(
+								#types::ErrorCode::InvalidParams.code(),
+								#types::ErrorCode::InvalidParams.message(),
 								Some(err.to_string()),
 							))?;
 					}
@@ -291,11 +430,11 @@ impl RpcDescription {
 				
 				let param_idents = sub.params.iter().map(|p| &p.arg_pat.ident);
 				quote! {{
-					let arr: Vec<serde_json::Value> = request.params().parse()?;
+					let arr: Vec<#serde_json::Value> = request.params().parse()?;
 					if arr.len() != #param_count {
-						return Err(jsonrpsee_types::ErrorObjectOwned::owned(
-							jsonrpsee_types::ErrorCode::InvalidParams.code(),
-							jsonrpsee_types::ErrorCode::InvalidParams.message(),
+						return Err(#types::ErrorObjectOwned::owned(
+							#types::ErrorCode::InvalidParams.code(),
+							#types::ErrorCode::InvalidParams.message(),
 							Some(format!("Expected {} parameters, got {}", #param_count, arr.len())),
 						));
 					}
@@ -310,21 +449,32 @@ impl RpcDescription {
 		});
 		
 		Ok(quote! {
-			impl<'a> TryFrom<jsonrpsee_types::Request<'a>> for #enum_name {
-				type Error = jsonrpsee_types::ErrorObjectOwned;
+			#[allow(deprecated)]
+			impl<'a> TryFrom<#types::Request<'a>> for #enum_name {
+				type Error = #types::ErrorObjectOwned;
 				
-				fn try_from(request: jsonrpsee_types::Request<'a>) -> Result<Self, Self::Error> {
+				fn try_from(request: #types::Request<'a>) -> Result<Self, Self::Error> {
 					match request.method.as_ref() {
 						#(#method_arms,)*
 						#(#sub_arms,)*
-						_ => Err(jsonrpsee_types::ErrorObjectOwned::from(jsonrpsee_types::ErrorCode::MethodNotFound))
+						_ => Err(#types::ErrorObjectOwned::from(#types::ErrorCode::MethodNotFound))
 					}
 				}
 			}
 		})
 	}
 	
-	fn render_server_layer(&self, layer_name: &syn::Ident, service_name: &syn::Ident, request_enum_name: &syn::Ident) -> Result<TokenStream2, syn::Error> {
+	fn render_server_layer(
+		&self,
+		layer_name: &syn::Ident,
+		service_name: &syn::Ident,
+		config_name: &syn::Ident,
+		request_enum_name: &syn::Ident,
+	) -> Result<TokenStream2, syn::Error> {
+		let krate = &self.crate_path;
+		let types = self.private_path("jsonrpsee_types");
+		let serde_json = self.private_path("serde_json");
+		let tower = self.private_path("tower");
 		let trait_name = &self.trait_def.ident;
 		let mut all_method_names = Vec::new();
 		for method in &self.methods {
@@ -334,9 +484,33 @@ impl RpcDescription {
 			all_method_names.extend(names_with_aliases(self.rpc_identifier(&sub.name), &sub.aliases));
 		}
 		let all_method_match = all_method_names.iter().map(|name| quote! { #name });
+		let method_info = self.methods.iter().map(|method| self.render_method_info(method));
+		// Only the methods reading the request parts, through extractors or the layers of
+		// their stack, copy them from the request.
+		let parts_arms = self.methods.iter().map(|method| {
+			let names = names_with_aliases(self.rpc_identifier(&method.name), &method.aliases);
+			let method_ident = &method.signature.sig.ident;
+			let needed = if method.args.iter().any(|arg| arg.extract) {
+				quote! { true }
+			} else {
+				quote! { config.stacks.#method_ident.is_some() }
+			};
+			quote! {
+				#(#names)|* if #needed => Some(#krate::extract::RequestParts::from_request(&json_request)),
+			}
+		});
+		let request_parts = (!self.methods.is_empty()).then(|| {
+			quote! {
+				let parts = match json_request.method.as_ref() {
+					#(#parts_arms)*
+					_ => None,
+				};
+			}
+		});
 		
 		let method_match_arms = self.methods.iter().map(|method| {
-			let variant_name = to_variant_name(&method.name);
+			let variant_name = &method.variant;
+			let method_name = self.rpc_identifier(&method.name);
 			let method_ident = &method.signature.sig.ident;
 			let param_idents: Vec<_> = method.params.iter().map(|param| &param.arg_pat.ident).collect();
 			let await_token = if method.signature.sig.asyncness.is_some() {
@@ -345,74 +519,188 @@ impl RpcDescription {
 				quote! {}
 			};
 			
-			let param_idents2 = param_idents.clone();
+			// Calls to deprecated methods are flagged on the response, so that outer layers can
+			// log them or turn them into a `Deprecation` header.
+			let mark_deprecated = method.deprecation.as_ref().map(|deprecation| {
+				let since = option_tokens(deprecation.since.as_deref());
+				let note = option_tokens(deprecation.note.as_deref());
+				quote! {
+					let mut response = response;
+					response.extensions_mut().insert(#krate::server::Deprecation {
+						method: #method_name,
+						since: #since,
+						note: #note,
+					});
+				}
+			});
+			
+			let arg_idents = method.args.iter().map(|arg| &arg.arg_pat.ident);
+			let call = quote! {
+				match <H as #trait_name>::#method_ident(&handler, #(#arg_idents),*)#await_token {
+					Ok(result) => {
+						let value = #serde_json::to_value(result).unwrap();
+						#types::Response::new(
+							#types::ResponsePayload::success(value),
+							request_id,
+						)
+					}
+					Err(err) => {
+						#types::Response::new(
+							#types::ResponsePayload::error(err),
+							request_id,
+						)
+					}
+				}
+			};
+			
+			// Extracted arguments are built from the request parts; a failing extractor
+			// answers the request with its error instead of calling the handler.
+			let has_extractors = method.args.iter().any(|arg| arg.extract);
+			let call = if has_extractors {
+				let extractions = method.args.iter().filter(|arg| arg.extract).map(|arg| {
+					let name = &arg.arg_pat.ident;
+					let ty = &arg.ty;
+					quote! {
+						let #name: #ty = match <#ty as #krate::extract::FromRequestParts>::from_request_parts(&parts) {
+							Ok(value) => value,
+							Err(err) => break 'response #types::Response::new(
+								#types::ResponsePayload::error(err),
+								request_id,
+							),
+						};
+					}
+				});
+				quote! {
+					'response: {
+						#(#extractions)*
+						#call
+					}
+				}
+			} else {
+				call
+			};
+			let unwrap_parts = |reason: &str| quote! { let parts = parts.expect(#reason); };
+			let (unwrap_parts, unwrap_stack_parts) = if has_extractors {
+				(Some(unwrap_parts("request parts are copied for methods with extractors")), None)
+			} else {
+				(None, Some(unwrap_parts("request parts are copied for methods with a stack")))
+			};
+			
+			// A method wrapped with layers through the builder is called through its stack.
+			let call = quote! {
+				match stack {
+							Some(stack) => {
+								#unwrap_stack_parts
+								let call = #krate::method::MethodCall { params: (#(#param_idents,)*), parts };
+								match #tower::ServiceExt::oneshot(stack, call).await {
+									Ok(result) => {
+										let value = #serde_json::to_value(result).unwrap();
+										#types::Response::new(
+											#types::ResponsePayload::success(value),
+											request_id,
+										)
+									}
+									Err(err) => {
+										#types::Response::new(
+											#types::ResponsePayload::error(#krate::method::into_error_object(err)),
+											request_id,
+										)
+									}
+								}
+							}
+							None => #call,
+						}
+			};
+			
+			// Methods with a timeout are raced against a timer, and answer with an error when it fires first.
+			let call = match method.timeout {
+				Some(timeout) => {
+					let timeout = render_duration(timeout);
+					quote! {{
+						let timeout_id = request_id.clone();
+						let timeout = #timeout;
+						match #krate::timeout::race(timeout, async { #call }).await {
+							Some(response) => response,
+							None => #types::Response::new(
+								#types::ResponsePayload::error(timeout_error),
+								timeout_id,
+							),
+						}
+					}}
+				}
+				None => call,
+			};
+			let timeout_error = method.timeout.is_some().then(|| {
+				quote! {
+					let timeout_error = config.timeout_error.clone().unwrap_or_else(#krate::timeout::timeout_error);
+				}
+			});
+			
 			quote! {
 				#request_enum_name::#variant_name { #(#param_idents),* } => {
 					let handler = handler.clone();
 					let request_id = request_id.clone();
+					let stack = config.stacks.#method_ident.clone();
+					#timeout_error
 					Box::pin(async move {
-						match handler.#method_ident(#(#param_idents2),*)#await_token {
-							Ok(result) => {
-								let value = serde_json::to_value(result).unwrap();
-								jsonrpsee_types::Response::new(
-									jsonrpsee_types::ResponsePayload::success(value),
-									request_id,
-								)
-							}
-							Err(err) => {
-								jsonrpsee_types::Response::new(
-									jsonrpsee_types::ResponsePayload::error(err),
-									request_id,
-								)
-							}
-						}
-					}) as ::tower_json_rpc::server::BoxFuture<
-						jsonrpsee_types::Response<'static, serde_json::Value>,
+						#unwrap_parts
+						let response = #call;
+						#mark_deprecated
+						response
+					}) as #krate::server::BoxFuture<
+						#types::Response<'static, #serde_json::Value>,
 					>
 				}
 			}
 		});
 		
 		let sub_match_arms = self.subscriptions.iter().map(|sub| {
-			let variant_name = to_variant_name(&sub.name);
+			let variant_name = &sub.variant;
 			quote! {
 				#request_enum_name::#variant_name { .. } => {
 					let request_id = request_id.clone();
 					Box::pin(async move {
-						jsonrpsee_types::Response::new(
-							jsonrpsee_types::ResponsePayload::error(
-								jsonrpsee_types::ErrorObjectOwned::owned(
-									jsonrpsee_types::ErrorCode::InvalidRequest.code(),
-									jsonrpsee_types::ErrorCode::InvalidRequest.message(),
+						#types::Response::new(
+							#types::ResponsePayload::error(
+								#types::ErrorObjectOwned::owned(
+									#types::ErrorCode::InvalidRequest.code(),
+									#types::ErrorCode::InvalidRequest.message(),
 									Some("Subscriptions not yet implemented"),
 								)
 							),
 							request_id,
 						)
-					}) as ::tower_json_rpc::server::BoxFuture<
-						jsonrpsee_types::Response<'static, serde_json::Value>,
+					}) as #krate::server::BoxFuture<
+						#types::Response<'static, #serde_json::Value>,
 					>
 				}
 			}
 		});
 		
+		let layer_doc = format!(" Layer serving the `{trait_name}` RPC API, forwarding any other method to the inner service.");
+		let service_doc = format!(" Service produced by [`{layer_name}`].");
+
 		Ok(quote! {
+			#[doc = #layer_doc]
 			pub struct #layer_name<H> {
-				handler: std::sync::Arc<H>,
+				handler: ::std::sync::Arc<H>,
+				config: ::std::sync::Arc<#config_name>,
 			}
 			
 			impl<H> #layer_name<H> 
 			where
 				H: #trait_name + Send + Sync + 'static
 			{
+				/// Creates a new layer dispatching requests to `handler`.
 				pub fn new(handler: H) -> Self {
 					Self {
-						handler: std::sync::Arc::new(handler)
+						handler: ::std::sync::Arc::new(handler),
+						config: ::std::sync::Arc::default(),
 					}
 				}
 			}
 			
-			impl<S, H> tower::Layer<S> for #layer_name<H>
+			impl<S, H> #tower::Layer<S> for #layer_name<H>
 			where
 				H: #trait_name + Send + Sync + 'static
 			{
@@ -422,57 +710,67 @@ impl RpcDescription {
 					#service_name {
 						inner,
 						handler: self.handler.clone(),
+						config: self.config.clone(),
 					}
 				}
 			}
 			
+			#[doc = #service_doc]
 			pub struct #service_name<S, H> {
 				inner: S,
-				handler: std::sync::Arc<H>,
+				handler: ::std::sync::Arc<H>,
+				config: ::std::sync::Arc<#config_name>,
 			}
 			
-			impl<S, H, Req> tower::Service<Req> for #service_name<S, H>
+			#[allow(deprecated)]
+			impl<S, H, Req> #tower::Service<Req> for #service_name<S, H>
 			where
-				Req: ::tower_json_rpc::server::ServerRequest,
-				S: tower::Service<jsonrpsee_types::Request<'static>, Response = jsonrpsee_types::Response<'static, serde_json::Value>> + Clone + Send + 'static,
+				Req: #krate::server::ServerRequest,
+				S: #tower::Service<#types::Request<'static>, Response = #types::Response<'static, #serde_json::Value>> + Clone + Send + 'static,
 				S::Future: Send + 'static,
-				S::Error: Into<::tower_json_rpc::error::JsonRpcError> + Send + 'static,
+				S::Error: Into<#krate::error::JsonRpcError> + Send + 'static,
 				H: #trait_name + Send + Sync + 'static
 			{
-				type Response = <Req as ::tower_json_rpc::server::ServerRequest>::Response;
-				type Error = ::tower_json_rpc::error::JsonRpcError;
-				type Future = ::tower_json_rpc::server::BoxFuture<Result<Self::Response, Self::Error>>;
+				type Response = <Req as #krate::server::ServerRequest>::Response;
+				type Error = #krate::error::JsonRpcError;
+				type Future = #krate::server::BoxFuture<Result<Self::Response, Self::Error>>;
 				
-				fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+				fn poll_ready(&mut self, cx: &mut ::std::task::Context<'_>) -> ::std::task::Poll<Result<(), Self::Error>> {
 					self.inner.poll_ready(cx).map_err(Into::into)
 				}
 				
 				fn call(&mut self, request: Req) -> Self::Future {
-					use ::tower_json_rpc::__private::futures_util::future::FutureExt;
+					use #krate::__private::futures_util::future::FutureExt;
 
 					let handler = self.handler.clone();
+					let config = self.config.clone();
 					let mut inner = self.inner.clone();
 					let fut = request.into_json_rpc_request();
 
 					Box::pin(fut.then(move |result| match result {
 						Ok(json_request) => {
-							let json_request: jsonrpsee_types::Request<'static> = json_request;
+							let mut json_request: #types::Request<'static> = json_request;
 							let request_id = json_request.id.clone();
+							let guard = #krate::cancel::DropGuard::arm(&mut json_request);
 
 							if !matches!(json_request.method.as_ref(), #(#all_method_match)|*) {
 								let service_fut = inner.call(json_request);
 								return Box::pin(
-									service_fut.then(move |service_result| match service_result {
-										Ok(response) => <Req::Response as ::tower_json_rpc::server::ServerResponse>::from_json_rpc_response(response),
-										Err(err) => Box::pin(async move { Err(err.into()) }),
+									service_fut.then(move |service_result| {
+										guard.disarm();
+										match service_result {
+											Ok(response) => <Req::Response as #krate::server::ServerResponse>::from_json_rpc_response(response),
+											Err(err) => Box::pin(async move { Err(err.into()) }),
+										}
 									}),
-								) as ::tower_json_rpc::server::BoxFuture<
-									Result<Req::Response, ::tower_json_rpc::error::JsonRpcError>,
+								) as #krate::server::BoxFuture<
+									Result<Req::Response, #krate::error::JsonRpcError>,
 								>;
 							}
 
-							let response_fut: ::tower_json_rpc::server::BoxFuture<
-								jsonrpsee_types::Response<'static, serde_json::Value>,
+							#request_parts
+							let response_fut: #krate::server::BoxFuture<
+								#types::Response<'static, #serde_json::Value>,
 							> = match #request_enum_name::try_from(json_request) {
 								Ok(parsed_request) => match parsed_request {
 									#(#method_match_arms)*
@@ -481,8 +779,8 @@ impl RpcDescription {
 								Err(err) => {
 									let request_id = request_id.clone();
 									Box::pin(async move {
-										jsonrpsee_types::Response::new(
-											jsonrpsee_types::ResponsePayload::error(err),
+										#types::Response::new(
+											#types::ResponsePayload::error(err),
 											request_id,
 										)
 									})
@@ -490,9 +788,10 @@ impl RpcDescription {
 							};
 
 							Box::pin(response_fut.then(move |response| {
-								<Req::Response as ::tower_json_rpc::server::ServerResponse>::from_json_rpc_response(response)
-							})) as ::tower_json_rpc::server::BoxFuture<
-								Result<Req::Response, ::tower_json_rpc::error::JsonRpcError>,
+								guard.disarm();
+								<Req::Response as #krate::server::ServerResponse>::from_json_rpc_response(response)
+							})) as #krate::server::BoxFuture<
+								Result<Req::Response, #krate::error::JsonRpcError>,
 							>
 						}
 						Err(err) => Box::pin(async move { Err(err) }),
@@ -500,6 +799,11 @@ impl RpcDescription {
 				}
 			}
 			
+			impl<S, H> #krate::router::RpcMethods for #service_name<S, H> {
+				const METHODS: &'static [&'static str] = &[#(#all_method_names),*];
+				const INFO: &'static [#krate::method::MethodInfo] = &[#(#method_info),*];
+			}
+			
 			impl<S, H> Clone for #service_name<S, H> 
 			where
 				S: Clone
@@ -507,7 +811,8 @@ impl RpcDescription {
 				fn clone(&self) -> Self {
 					Self {
 						inner: self.inner.clone(),
-						handler: self.handler.clone()
+						handler: self.handler.clone(),
+						config: self.config.clone(),
 					}
 				}
 			}
@@ -515,25 +820,193 @@ impl RpcDescription {
 	}
 }
 
-fn to_variant_name(method_name: &str) -> syn::Ident {
-	let mut result = String::new();
-	let mut capitalize_next = true;
-	
-	for ch in method_name.chars() {
-		if ch == '_' || ch == '-' {
-			capitalize_next = true;
-		} else if capitalize_next {
-			result.push(ch.to_ascii_uppercase());
-			capitalize_next = false;
-		} else {
-			result.push(ch);
+impl RpcDescription {
+	/// Renders `<Trait>ServerBuilder`, which wraps the calls of single methods with tower layers, and the
+	/// `<Trait>MethodStacks` holding the resulting per-method services.
+	fn render_server_builder(
+		&self,
+		layer_name: &syn::Ident,
+		builder_name: &syn::Ident,
+		config_name: &syn::Ident,
+		stacks_name: &syn::Ident,
+	) -> Result<TokenStream2, syn::Error> {
+		let krate = &self.crate_path;
+		let types = self.private_path("jsonrpsee_types");
+		let tower = self.private_path("tower");
+		let trait_name = &self.trait_def.ident;
+
+		let mut fields = Vec::new();
+		let mut setters = Vec::new();
+		for method in &self.methods {
+			let method_ident = &method.signature.sig.ident;
+			let setter = quote::format_ident!("{}_layer", method_ident.unraw());
+			let param_idents: Vec<_> = method.params.iter().map(|param| &param.arg_pat.ident).collect();
+			let param_tys = method.params.iter().map(|param| &param.ty);
+			let params_ty = quote! { (#(#param_tys,)*) };
+			let ok_ty = ok_type(&method.signature.sig.output);
+			let await_token = method.signature.sig.asyncness.is_some().then(|| quote! { .await });
+			let arg_idents = method.args.iter().map(|arg| &arg.arg_pat.ident);
+			let extractions = method.args.iter().filter(|arg| arg.extract).map(|arg| {
+				let name = &arg.arg_pat.ident;
+				let ty = &arg.ty;
+				quote! {
+					let #name = <#ty as #krate::extract::FromRequestParts>::from_request_parts(&call.parts)?;
+				}
+			});
+			let doc = format!(" Wraps the calls of the `{}` method with `layer`.", self.rpc_identifier(&method.name));
+			let service = quote! { #krate::method::MethodService<#params_ty, #ok_ty> };
+			let call = quote! { #krate::method::MethodCall<#params_ty> };
+
+			fields.push(quote! {
+				#method_ident: Option<#service>,
+			});
+			setters.push(quote! {
+				#[doc = #doc]
+				///
+				/// The layer wraps a service calling the handler. Setting a layer again replaces the previous one.
+				pub fn #setter<L>(mut self, layer: L) -> Self
+				where
+					L: #tower::Layer<#service>,
+					L::Service: #tower::Service<#call, Response = #ok_ty> + Clone + Send + Sync + 'static,
+					<L::Service as #tower::Service<#call>>::Error: Into<#tower::BoxError>,
+					<L::Service as #tower::Service<#call>>::Future: Send + 'static,
+				{
+					let handler = self.handler.clone();
+					let service = #tower::service_fn(move |call: #call| {
+						let handler = handler.clone();
+						async move {
+							#(#extractions)*
+							let (#(#param_idents,)*) = call.params;
+							<H as #trait_name>::#method_ident(&handler, #(#arg_idents),*)#await_token
+								.map_err(|err| #types::ErrorObjectOwned::from(err))
+						}
+					});
+					let service = #tower::util::BoxCloneSyncService::new(#tower::ServiceExt::map_err(
+						service,
+						#tower::BoxError::from,
+					));
+					let service = #tower::ServiceExt::map_err(layer.layer(service), Into::into);
+					self.config.stacks.#method_ident = Some(#tower::util::BoxCloneSyncService::new(service));
+					self
+				}
+			});
+		}
+
+		let builder_doc = format!(" Builder of a [`{layer_name}`] whose methods are wrapped with their own tower layers.");
+
+		Ok(quote! {
+			#[doc(hidden)]
+			#[derive(Default)]
+			pub struct #stacks_name {
+				#(#fields)*
+			}
+
+			#[doc(hidden)]
+			#[derive(Default)]
+			pub struct #config_name {
+				stacks: #stacks_name,
+				timeout_error: Option<#types::ErrorObjectOwned>,
+			}
+
+			#[doc = #builder_doc]
+			pub struct #builder_name<H> {
+				handler: ::std::sync::Arc<H>,
+				config: #config_name,
+			}
+
+			impl<H> #layer_name<H>
+			where
+				H: #trait_name + Send + Sync + 'static
+			{
+				/// Creates a builder of a layer dispatching requests to `handler`.
+				pub fn builder(handler: H) -> #builder_name<H> {
+					#builder_name {
+						handler: ::std::sync::Arc::new(handler),
+						config: #config_name::default(),
+					}
+				}
+			}
+
+			#[allow(deprecated)]
+			impl<H> #builder_name<H>
+			where
+				H: #trait_name + Send + Sync + 'static
+			{
+				#(#setters)*
+
+				/// Sets the error returned when a method with a `timeout` takes too long, instead of
+				/// `-32000 request timed out`.
+				pub fn timeout_error(mut self, error: #types::ErrorObjectOwned) -> Self {
+					self.config.timeout_error = Some(error);
+					self
+				}
+
+				/// Builds the layer.
+				pub fn build(self) -> #layer_name<H> {
+					#layer_name {
+						handler: self.handler,
+						config: ::std::sync::Arc::new(self.config),
+					}
+				}
+			}
+		})
+	}
+}
+
+impl RpcDescription {
+	/// Renders the `MethodInfo` describing the attributes of `method`.
+	fn render_method_info(&self, method: &RpcMethod) -> TokenStream2 {
+		let krate = &self.crate_path;
+		let name = self.rpc_identifier(&method.name);
+		let aliases = &method.aliases;
+		let timeout = method.timeout.map(|timeout| {
+			let timeout = render_duration(timeout);
+			quote! { .with_timeout(#timeout) }
+		});
+
+		let requires = method.requires.as_ref().map(|role| quote! { .with_requires(#role) });
+		let rate_limit = method.rate_limit.map(|rate_limit| {
+			let requests = rate_limit.requests;
+			let period = render_duration(rate_limit.period);
+			quote! { .with_rate_limit(#krate::rate_limit::RateLimit::new(#requests, #period)) }
+		});
+		let cost = method.cost.as_ref().map(|cost| match cost {
+			Cost::Fixed(units) => quote! { .with_cost(#krate::cost::Cost::Fixed(#units)) },
+			Cost::Dynamic(path) => quote! { .with_cost(#krate::cost::Cost::Dynamic(#path)) },
+		});
+		let cacheable = method.cacheable.then(|| quote! { .with_cacheable() });
+		let ttl = method.ttl.map(|ttl| {
+			let ttl = render_duration(ttl);
+			quote! { .with_ttl(#ttl) }
+		});
+
+		quote! {
+			#krate::method::MethodInfo::new(#name)
+				.with_aliases(&[#(#aliases),*])
+				#timeout
+				#requires
+				#rate_limit
+				#cost
+				#cacheable
+				#ttl
 		}
 	}
+}
+
+/// Renders `duration` as a `Duration` expression, keeping its sub-millisecond part.
+fn render_duration(duration: Duration) -> TokenStream2 {
+	let (secs, nanos) = (duration.as_secs(), duration.subsec_nanos());
+	quote! { ::core::time::Duration::new(#secs, #nanos) }
+}
 
-	syn::Ident::new(&result, proc_macro2::Span::call_site())
+fn option_tokens(value: Option<&str>) -> TokenStream2 {
+	match value {
+		Some(value) => quote! { Some(#value) },
+		None => quote! { None },
+	}
 }
 
-fn names_with_aliases(primary: Cow<'_, str>, aliases: &[String]) -> Vec<String> {
+pub(crate) fn names_with_aliases(primary: Cow<'_, str>, aliases: &[String]) -> Vec<String> {
 	let mut names = Vec::with_capacity(1 + aliases.len());
 	names.push(primary.to_string());
 	names.extend(aliases.iter().cloned());
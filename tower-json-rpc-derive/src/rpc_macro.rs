@@ -27,12 +27,17 @@
 //! Declaration of the JSON RPC generator procedural macros.
 
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::time::Duration;
 
 use crate::attributes::{
-	Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind, optional, parse_param_kind,
+	Aliases, Argument, AttributeMeta, Cost, Deprecation, MissingArgument, NameMapping, ParamKind, RateLimit, optional,
+	parse_param_kind,
 };
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
 use syn::{Attribute, Token, punctuated::Punctuated};
 
@@ -44,18 +49,25 @@ pub struct RpcFnArg {
 	pub(crate) arg_pat: syn::PatIdent,
 	rename_to: Option<String>,
 	pub(crate) ty: syn::Type,
+	/// Filled from the request parts with `FromRequestParts` instead of from `params`.
+	pub(crate) extract: bool,
 }
 
 impl RpcFnArg {
 	pub fn from_arg_attrs(arg_pat: syn::PatIdent, ty: syn::Type, attrs: &mut Vec<syn::Attribute>) -> syn::Result<Self> {
 		let mut rename_to = None;
+		let mut extract = false;
 
 		if let Some(attr) = find_attr(attrs, "argument") {
-			let [rename] = AttributeMeta::parse(attr.clone())?.retain(["rename"])?;
+			let [extract_arg, rename] = AttributeMeta::parse(attr.clone())?.retain(["extract", "rename"])?;
 
+			extract = optional(extract_arg, Argument::flag)?.is_some();
 			let rename = optional(rename, Argument::string)?;
 
 			if let Some(rename) = rename {
+				if extract {
+					return Err(syn::Error::new_spanned(attr, "Extracted arguments are not (de)serialized and can't be renamed"));
+				}
 				rename_to = Some(rename);
 			}
 		}
@@ -63,8 +75,9 @@ impl RpcFnArg {
 		// remove argument attribute after inspection
 		attrs.retain(|attr| !attr.meta.path().is_ident("argument"));
 
-	Ok(Self { arg_pat, rename_to, ty })
-}
+		Ok(Self { arg_pat, rename_to, ty, extract })
+	}
+
 	/// Return the string representation of this argument when (de)seriaizing.
 	pub fn name(&self) -> String {
 		self.rename_to.clone().unwrap_or_else(|| self.arg_pat.ident.to_string())
@@ -74,37 +87,114 @@ impl RpcFnArg {
 #[derive(Debug, Clone)]
 pub struct RpcMethod {
 	pub name: String,
+	/// Name of the variant in the generated request and response enums.
+	pub variant: syn::Ident,
+	/// Arguments sent on the wire as `params`.
 	pub params: Vec<RpcFnArg>,
+	/// All arguments of the handler method in declaration order, including extracted ones.
+	pub args: Vec<RpcFnArg>,
 	pub param_kind: ParamKind,
 	pub signature: syn::TraitItemFn,
 	pub aliases: Vec<String>,
+	/// Doc comments of the trait method, forwarded to the generated items.
+	pub docs: TokenStream2,
+	pub deprecation: Option<Deprecation>,
+	/// Time after which the server answers with a timeout error, including the trait-level default.
+	pub timeout: Option<Duration>,
+	/// Role the caller must have, including the trait-level default.
+	pub requires: Option<String>,
+	/// Maximum rate of calls of each caller, including the trait-level default.
+	pub rate_limit: Option<RateLimit>,
+	/// Compute units charged per call, including the trait-level default.
+	pub cost: Option<Cost>,
+	/// Whether the results can be cached, for `ttl` or the default TTL of the cache.
+	pub cacheable: bool,
+	pub ttl: Option<Duration>,
+}
+
+/// Arguments of the `#[method]` attribute, defaulting to those of a method without one.
+#[derive(Default)]
+struct MethodAttrs {
+	aliases: Vec<String>,
+	blocking: bool,
+	cacheable: bool,
+	cost: Option<Cost>,
+	name: Option<String>,
+	param_kind: ParamKind,
+	rate_limit: Option<RateLimit>,
+	requires: Option<String>,
+	timeout: Option<Duration>,
+	ttl: Option<Duration>,
+	variant: Option<syn::Ident>,
+	with_extensions: bool,
+}
+
+impl MethodAttrs {
+	fn parse(attr: Attribute) -> syn::Result<Self> {
+		let [aliases, blocking, cacheable, cost, name, param_kind, rate_limit, requires, timeout, ttl, variant, with_extensions] =
+			AttributeMeta::parse(attr)?.retain([
+				"aliases",
+				"blocking",
+				"cacheable",
+				"cost",
+				"name",
+				"param_kind",
+				"rate_limit",
+				"requires",
+				"timeout",
+				"ttl",
+				"variant",
+				"with_extensions",
+			])?;
+
+		let cacheable = optional(cacheable, Argument::flag)?.is_some();
+		let ttl = match ttl {
+			Ok(arg) if !cacheable => return Err(syn::Error::new(arg.label.span(), "`ttl` requires `cacheable`")),
+			ttl => optional(ttl, Argument::duration)?,
+		};
+
+		Ok(Self {
+			aliases: parse_aliases(aliases)?,
+			blocking: optional(blocking, Argument::flag)?.is_some(),
+			cacheable,
+			cost: optional(cost, Argument::cost)?,
+			name: optional(name, Argument::string)?,
+			param_kind: parse_param_kind(param_kind)?,
+			rate_limit: optional(rate_limit, Argument::rate_limit)?,
+			requires: optional(requires, Argument::string)?,
+			timeout: optional(timeout, Argument::duration)?,
+			ttl,
+			variant: parse_variant(variant)?,
+			with_extensions: optional(with_extensions, Argument::flag)?.is_some(),
+		})
+	}
 }
 
 impl RpcMethod {
 	pub fn from_item(attr: Option<Attribute>, mut method: syn::TraitItemFn) -> syn::Result<Self> {
-		let (aliases, blocking, name, param_kind) = if let Some(attr) = attr {
-			let [aliases, blocking, name, param_kind, with_extensions] =
-				AttributeMeta::parse(attr)?.retain(["aliases", "blocking", "name", "param_kind", "with_extensions"])?;
-
-			let aliases = parse_aliases(aliases)?;
-			let blocking = optional(blocking, Argument::flag)?.is_some();
-			// Use explicit name if provided, otherwise convert snake_case to camelCase
-			let name = optional(name, Argument::string)?
-				.unwrap_or_else(|| snake_to_camel(&method.sig.ident.to_string()));
-			let param_kind = parse_param_kind(param_kind)?;
-			let _with_extensions = optional(with_extensions, Argument::flag)?.is_some();
-
-			(aliases, blocking, name, param_kind)
-		} else {
-			// No attribute - use defaults, converting snake_case to camelCase
-			(Vec::new(), false, snake_to_camel(&method.sig.ident.to_string()), ParamKind::Array)
-		};
+		let MethodAttrs {
+			aliases,
+			blocking,
+			cacheable,
+			cost,
+			name,
+			param_kind,
+			rate_limit,
+			requires,
+			timeout,
+			ttl,
+			variant,
+			with_extensions,
+		} = attr.map(MethodAttrs::parse).transpose()?.unwrap_or_default();
+		// Use explicit name if provided, otherwise convert snake_case to camelCase
+		let name = name.unwrap_or_else(|| snake_to_camel(&method.sig.ident.to_string()));
+		let variant = variant.unwrap_or_else(|| to_variant_name(&method.sig.ident));
 
 		if blocking && method.sig.asyncness.is_some() {
 			return Err(syn::Error::new(method.sig.span(), "Blocking method must be synchronous"));
 		}
 
-		let params: Vec<_> = method
+		let args: Vec<_> = method
 			.sig
 			.inputs
 			.iter_mut()
@@ -125,6 +215,10 @@ impl RpcMethod {
 				},
 			})
 			.collect::<Result<_, _>>()?;
+		let params = split_extracted_args(&args, with_extensions, &method.sig)?;
+
+		let docs = extract_doc_comments(&method.attrs);
+		let deprecation = find_attr(&method.attrs, "deprecated").map(Deprecation::parse).transpose()?;
 
 		// We've analyzed attributes and don't need them anymore.
 		method.attrs.clear();
@@ -132,9 +226,19 @@ impl RpcMethod {
 		Ok(Self {
 			aliases,
 			name,
+			variant,
 			params,
+			args,
 			param_kind,
 			signature: method,
+			docs,
+			deprecation,
+			timeout,
+			requires,
+			rate_limit,
+			cost,
+			cacheable,
+			ttl,
 		})
 	}
 }
@@ -142,19 +246,27 @@ impl RpcMethod {
 #[derive(Debug, Clone)]
 pub struct RpcSubscription {
 	pub name: String,
+	/// Name of the variant in the generated request enum.
+	pub variant: syn::Ident,
 	pub unsubscribe: String,
 	#[allow(dead_code)]
 	pub notif_name: String,
 	pub item: syn::Type,
+	/// Arguments sent on the wire as `params`.
 	pub params: Vec<RpcFnArg>,
+	/// All arguments of the handler method in declaration order, including extracted ones.
+	pub args: Vec<RpcFnArg>,
 	pub param_kind: ParamKind,
 	pub aliases: Vec<String>,
 	pub signature: syn::TraitItemFn,
+	/// Doc comments of the trait method, forwarded to the generated items.
+	pub docs: TokenStream2,
+	pub deprecation: Option<Deprecation>,
 }
 
 impl RpcSubscription {
 	pub fn from_item(attr: syn::Attribute, mut sub: syn::TraitItemFn) -> syn::Result<Self> {
-		let [aliases, item, name, param_kind, unsubscribe, unsubscribe_aliases, with_extensions] =
+		let [aliases, item, name, param_kind, unsubscribe, unsubscribe_aliases, variant, with_extensions] =
 			AttributeMeta::parse(attr)?.retain([
 				"aliases",
 				"item",
@@ -162,6 +274,7 @@ impl RpcSubscription {
 				"param_kind",
 				"unsubscribe",
 				"unsubscribe_aliases",
+				"variant",
 				"with_extensions",
 			])?;
 
@@ -172,7 +285,8 @@ impl RpcSubscription {
 		let item: syn::Type = item?.value()?;
 		let param_kind = parse_param_kind(param_kind)?;
 		let _unsubscribe_aliases = parse_aliases(unsubscribe_aliases)?;
-		let _with_extensions = optional(with_extensions, Argument::flag)?.is_some();
+		let variant = parse_variant(variant)?.unwrap_or_else(|| to_variant_name(&sub.sig.ident));
+		let with_extensions = optional(with_extensions, Argument::flag)?.is_some();
 
 		let unsubscribe = match parse_subscribe(unsubscribe)? {
 			Some(unsub) => unsub,
@@ -181,7 +295,7 @@ impl RpcSubscription {
 			),
 		};
 
-		let params: Vec<_> = sub
+		let args: Vec<_> = sub
 			.sig
 			.inputs
 			.iter_mut()
@@ -195,19 +309,27 @@ impl RpcSubscription {
 				},
 			})
 			.collect::<Result<_, _>>()?;
+		let params = split_extracted_args(&args, with_extensions, &sub.sig)?;
+
+		let docs = extract_doc_comments(&sub.attrs);
+		let deprecation = find_attr(&sub.attrs, "deprecated").map(Deprecation::parse).transpose()?;
 
 		// We've analyzed attributes and don't need them anymore.
 		sub.attrs.clear();
 
 		Ok(Self {
 			name,
+			variant,
 			unsubscribe,
 			notif_name,
 			item,
 			params,
+			args,
 			param_kind,
 			aliases,
 			signature: sub,
+			docs,
+			deprecation,
 		})
 	}
 }
@@ -220,10 +342,14 @@ pub struct RpcDescription {
 	pub(crate) needs_server: bool,
 	/// Switch denoting that client trait must be generated.
 	pub(crate) needs_client: bool,
+	/// Switch denoting that `Serialize` and `Deserialize` must be implemented for the generated enums.
+	pub(crate) needs_serde: bool,
 	/// Optional prefix for RPC namespace.
 	pub(crate) namespace: Option<String>,
 	/// Optional separator between namespace and method name. Defaults to `_`.
 	pub(crate) namespace_separator: Option<String>,
+	/// Path to the `tower_json_rpc` crate used by the generated code. Defaults to `::tower_json_rpc`.
+	pub(crate) crate_path: syn::Path,
 	/// Trait definition in which all the attributes were stripped.
 	pub(crate) trait_def: syn::ItemTrait,
 	/// List of RPC methods defined in the trait.
@@ -234,7 +360,7 @@ pub struct RpcDescription {
 
 impl RpcDescription {
 	pub fn from_item(attr: Attribute, mut item: syn::ItemTrait) -> syn::Result<Self> {
-		let [client, server, namespace, namespace_separator, client_bounds, server_bounds] =
+		let [client, server, namespace, namespace_separator, client_bounds, server_bounds, crate_path, serde, timeout, requires, rate_limit, cost] =
 			AttributeMeta::parse(attr)?.retain([
 				"client",
 				"server",
@@ -242,12 +368,25 @@ impl RpcDescription {
 				"namespace_separator",
 				"client_bounds",
 				"server_bounds",
+				"crate",
+				"serde",
+				"timeout",
+				"requires",
+				"rate_limit",
+				"cost",
 			])?;
 
 		let needs_server = optional(server, Argument::flag)?.is_some();
 		let needs_client = optional(client, Argument::flag)?.is_some();
+		let needs_serde = optional(serde, Argument::flag)?.is_some();
 		let namespace = optional(namespace, Argument::string)?;
 		let namespace_separator = optional(namespace_separator, Argument::string)?;
+		let timeout = optional(timeout, Argument::duration)?;
+		let requires = optional(requires, Argument::string)?;
+		let rate_limit = optional(rate_limit, Argument::rate_limit)?;
+		let cost = optional(cost, Argument::cost)?;
+		let crate_path = optional(crate_path, |arg| arg.value::<syn::LitStr>()?.parse::<syn::Path>())?
+			.unwrap_or_else(|| syn::parse_quote!(::tower_json_rpc));
 		let _client_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>> =
 			optional(client_bounds, Argument::group)?;
 		let _server_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>> =
@@ -298,7 +437,11 @@ impl RpcDescription {
 					subscriptions.push(sub_data);
 				} else {
 					// Treat as a method (with or without #[method] attribute)
-					let method_data = RpcMethod::from_item(method_attr.cloned(), method.clone())?;
+					let mut method_data = RpcMethod::from_item(method_attr.cloned(), method.clone())?;
+					method_data.timeout = method_data.timeout.or(timeout);
+					method_data.requires = method_data.requires.take().or_else(|| requires.clone());
+					method_data.rate_limit = method_data.rate_limit.or(rate_limit);
+					method_data.cost = method_data.cost.take().or_else(|| cost.clone());
 					methods.push(method_data);
 				}
 			} else {
@@ -310,32 +453,84 @@ impl RpcDescription {
 			return Err(syn::Error::new_spanned(&item, "RPC cannot be empty"));
 		}
 
-		strip_rpc_attrs(&mut item);
-		rewrite_async_methods(&mut item);
-
-		Ok(Self {
+		let mut description = Self {
 			needs_server,
 			needs_client,
+			needs_serde,
 			namespace,
 			namespace_separator,
+			crate_path,
 			trait_def: item,
 			methods,
 			subscriptions,
-		})
+		};
+		description.check_name_collisions()?;
+
+		strip_rpc_attrs(&mut description.trait_def);
+		rewrite_async_methods(&mut description.trait_def);
+
+		Ok(description)
+	}
+
+	/// Checks that the RPC names (including aliases) and the generated enum variants are unique.
+	fn check_name_collisions(&self) -> syn::Result<()> {
+		let mut names = HashSet::new();
+		let mut variants: HashMap<String, &syn::Ident> = HashMap::new();
+
+		let methods = self.methods.iter().map(|method| {
+			let names = iter::once(self.rpc_identifier(&method.name).into_owned()).chain(method.aliases.iter().cloned());
+			(&method.signature.sig.ident, &method.variant, names.collect::<Vec<_>>())
+		});
+		let subscriptions = self.subscriptions.iter().map(|sub| {
+			let names = iter::once(self.rpc_identifier(&sub.name).into_owned())
+				.chain(sub.aliases.iter().cloned())
+				.chain(iter::once(self.rpc_identifier(&sub.unsubscribe).into_owned()));
+			(&sub.signature.sig.ident, &sub.variant, names.collect::<Vec<_>>())
+		});
+
+		for (ident, variant, rpc_names) in methods.chain(subscriptions) {
+			for name in rpc_names {
+				if !names.insert(name.clone()) {
+					return Err(syn::Error::new_spanned(ident, format!("{name:?} is already defined")));
+				}
+			}
+
+			if let Some(other) = variants.insert(variant.to_string(), ident) {
+				return Err(syn::Error::new_spanned(
+					ident,
+					format!(
+						"Variant `{variant}` of `{ident}` collides with the one of `{other}`; rename it with `variant = \"...\"`"
+					),
+				));
+			}
+		}
+
+		Ok(())
 	}
 
 	pub fn render(self) -> Result<TokenStream2, syn::Error> {
 		let trait_def = &self.trait_def;
 		let server_impl = if self.needs_server { self.render_server()? } else { TokenStream2::new() };
 		let client_impl = if self.needs_client { self.render_client()? } else { TokenStream2::new() };
+		let serde_impl = if self.needs_serde { self.render_serde()? } else { TokenStream2::new() };
 
 		Ok(quote! {
 			#trait_def
 			#server_impl
 			#client_impl
+			#serde_impl
 		})
 	}
 
+	/// Path to a dependency of the generated code, re-exported from `tower_json_rpc::__private`
+	/// so that users don't have to depend on it directly.
+	pub(crate) fn private_path(&self, dependency: &str) -> TokenStream2 {
+		let crate_path = &self.crate_path;
+		let dependency = syn::Ident::new(dependency, proc_macro2::Span::call_site());
+
+		quote! { #crate_path::__private::#dependency }
+	}
+
 	/// Based on the namespace and separator, renders the full name of the RPC method/subscription.
 	/// Examples:
 	/// For namespace `foo`, method `makeSpam`, and separator `_`, result will be `foo_makeSpam`.
@@ -357,6 +552,17 @@ fn parse_aliases(arg: Result<Argument, MissingArgument>) -> syn::Result<Vec<Stri
 	Ok(aliases.map(|a| a.list.into_iter().map(|lit| lit.value()).collect()).unwrap_or_default())
 }
 
+fn parse_variant(arg: Result<Argument, MissingArgument>) -> syn::Result<Option<syn::Ident>> {
+	let variant = optional(arg, Argument::value::<syn::LitStr>)?;
+
+	variant
+		.map(|lit| {
+			lit.parse::<syn::Ident>()
+				.map_err(|_| syn::Error::new(lit.span(), format!("{:?} is not a valid variant name", lit.value())))
+		})
+		.transpose()
+}
+
 fn parse_subscribe(arg: Result<Argument, MissingArgument>) -> syn::Result<Option<String>> {
 	let unsub = optional(arg, Argument::string)?;
 
@@ -367,10 +573,61 @@ fn find_attr<'a>(attrs: &'a [Attribute], ident: &str) -> Option<&'a Attribute> {
 	attrs.iter().find(|a| a.path().is_ident(ident))
 }
 
+/// Returns the arguments that are sent as `params`, checking that extracted arguments are
+/// only used on methods marked `with_extensions`.
+fn split_extracted_args(args: &[RpcFnArg], with_extensions: bool, sig: &syn::Signature) -> syn::Result<Vec<RpcFnArg>> {
+	if let Some(arg) = args.iter().find(|arg| arg.extract)
+		&& !with_extensions
+	{
+		return Err(syn::Error::new_spanned(
+			&arg.arg_pat,
+			format!("`#[argument(extract)]` requires `with_extensions` on the `{}` method attribute", sig.ident),
+		));
+	}
+
+	Ok(args.iter().filter(|arg| !arg.extract).cloned().collect())
+}
+
+/// Collects the `///` doc comments (`#[doc = "..."]` attributes) from `attrs`.
+fn extract_doc_comments(attrs: &[Attribute]) -> TokenStream2 {
+	let docs = attrs.iter().filter(|attr| {
+		attr.path().is_ident("doc")
+			&& matches!(
+				&attr.meta,
+				syn::Meta::NameValue(syn::MetaNameValue {
+					value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(_), .. }),
+					..
+				})
+			)
+	});
+
+	quote! { #(#docs)* }
+}
+
 fn build_unsubscribe_method(method: &str) -> Option<String> {
 	method.strip_prefix("subscribe").map(|s| format!("unsubscribe{s}"))
 }
 
+/// Converts the Rust method identifier into an enum variant name.
+/// Examples: "say_hello" -> "SayHello", "r#type" -> "Type", "blockNumber" -> "BlockNumber"
+fn to_variant_name(ident: &syn::Ident) -> syn::Ident {
+	let mut result = String::new();
+	let mut capitalize_next = true;
+
+	for ch in ident.unraw().to_string().chars() {
+		if ch == '_' {
+			capitalize_next = true;
+		} else if capitalize_next {
+			result.push(ch.to_ascii_uppercase());
+			capitalize_next = false;
+		} else {
+			result.push(ch);
+		}
+	}
+
+	syn::Ident::new(&result, ident.span())
+}
+
 /// Converts snake_case to camelCase.
 /// Examples: "block_number" -> "blockNumber", "get_block_by_hash" -> "getBlockByHash"
 fn snake_to_camel(s: &str) -> String {
@@ -31,7 +31,7 @@ impl Say for SayImpl {
 #[test]
 fn test_enum_generation() {
     // Test that the enum is generated
-    let request = SayRequest::Hello { param_0: true };
+    let request = SayRequest::SayHello { param_0: true };
 
     // Test Into<Request>
     let _json_request: jsonrpsee_types::Request<'static> = request.into();
@@ -52,7 +52,7 @@ fn test_try_from_request() {
     assert!(parsed.is_ok());
 
     match parsed.unwrap() {
-        SayRequest::Hello { param_0 } => {
+        SayRequest::SayHello { param_0 } => {
             assert_eq!(param_0, true);
         }
         _ => panic!("Wrong variant"),
@@ -110,6 +110,54 @@ async fn test_service_layer() {
     ));
 }
 
+#[tokio::test]
+async fn test_service_layer_dyn_handler() {
+    use std::sync::Arc;
+    use tower::{Layer, ServiceExt};
+
+    struct ShoutImpl;
+
+    impl Say for ShoutImpl {
+        async fn say_hello(&self, _param_0: bool) -> Result<String, ErrorObjectOwned> {
+            Ok("HELLO!".to_string())
+        }
+
+        fn say_goodbye(&self, name: String) -> Result<String, ErrorObjectOwned> {
+            Ok(format!("GOODBYE, {name}!"))
+        }
+    }
+
+    let inner = tower::service_fn(|req: jsonrpsee_types::Request<'static>| async move {
+        Ok::<_, std::convert::Infallible>(
+            jsonrpsee_types::Response::<'static, serde_json::Value>::new(
+                jsonrpsee_types::ResponsePayload::success(serde_json::json!("inner")),
+                req.id,
+            ),
+        )
+    });
+
+    for (shout, expected) in [(false, "Hello!"), (true, "HELLO!")] {
+        // The handler implementation is chosen at runtime.
+        let handler: Arc<dyn DynSay> = if shout {
+            Arc::new(ShoutImpl)
+        } else {
+            Arc::new(SayImpl)
+        };
+        let service = SayServerLayer::new(handler).layer(inner);
+
+        let request: jsonrpsee_types::Request<'static> =
+            SayRequest::SayHello { param_0: true }.into();
+        let response = ServiceExt::<jsonrpsee_types::Request<'static>>::oneshot(service, request)
+            .await
+            .unwrap();
+        let payload = serde_json::to_value(response).unwrap();
+        assert_eq!(payload["result"], expected);
+    }
+
+    let handler: Box<dyn DynSay> = Box::new(SayImpl);
+    assert_eq!(handler.say_goodbye("Ada".into()).unwrap(), "Goodbye, Ada!");
+}
+
 #[tokio::test]
 async fn test_service_layer_fallback() {
     use tower::{Layer, Service, ServiceExt};
@@ -146,3 +194,683 @@ async fn test_service_layer_fallback() {
         Some("inner")
     );
 }
+
+#[rpc(server, namespace = "legacy")]
+pub trait Legacy {
+    /// Replaced by `say_hello`.
+    #[deprecated(since = "0.2.0", note = "use `say_hello` instead")]
+    #[method(name = "hello")]
+    fn hello(&self) -> Result<String, ErrorObjectOwned>;
+}
+
+struct LegacyImpl;
+
+#[allow(deprecated)]
+impl Legacy for LegacyImpl {
+    fn hello(&self) -> Result<String, ErrorObjectOwned> {
+        Ok("Hello!".to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_deprecated_method_is_flagged() {
+    use tower::{Layer, ServiceExt};
+    use tower_json_rpc::router::MethodNotFound;
+
+    let service = LegacyServerLayer::new(LegacyImpl).layer(MethodNotFound);
+
+    let request: jsonrpsee_types::Request<'static> = jsonrpsee_types::Request::owned(
+        "legacy_hello".to_string(),
+        None,
+        jsonrpsee_types::Id::Number(1),
+    );
+
+    let response = ServiceExt::<jsonrpsee_types::Request<'static>>::oneshot(service, request)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response
+            .extensions()
+            .get::<tower_json_rpc::server::Deprecation>(),
+        Some(&tower_json_rpc::server::Deprecation {
+            method: "legacy_hello",
+            since: Some("0.2.0"),
+            note: Some("use `say_hello` instead"),
+        })
+    );
+}
+
+#[rpc(server, client, namespace = "auth")]
+pub trait Auth {
+    #[method(name = "whoami", with_extensions)]
+    async fn whoami(
+        &self,
+        #[argument(extract)] id: tower_json_rpc::extract::RequestId,
+        prefix: String,
+        #[argument(extract)] user: tower_json_rpc::extract::Extension<String>,
+    ) -> Result<String, ErrorObjectOwned>;
+}
+
+struct AuthImpl;
+
+impl Auth for AuthImpl {
+    async fn whoami(
+        &self,
+        id: tower_json_rpc::extract::RequestId,
+        prefix: String,
+        user: tower_json_rpc::extract::Extension<String>,
+    ) -> Result<String, ErrorObjectOwned> {
+        Ok(format!("{prefix}{} ({})", user.0, id.0))
+    }
+}
+
+#[tokio::test]
+async fn test_extracted_arguments() {
+    use tower::{Layer, ServiceExt};
+    use tower_json_rpc::router::MethodNotFound;
+
+    let service = AuthServerLayer::new(AuthImpl).layer(MethodNotFound);
+
+    // Extracted arguments are not part of the wire format.
+    let mut request: jsonrpsee_types::Request<'static> = AuthRequest::Whoami {
+        prefix: "user: ".to_string(),
+    }
+    .into();
+    request.id = jsonrpsee_types::Id::Number(4);
+    request.extensions.insert("ada".to_string());
+
+    let response =
+        ServiceExt::<jsonrpsee_types::Request<'static>>::oneshot(service.clone(), request)
+            .await
+            .unwrap();
+    let payload = serde_json::to_value(response).unwrap();
+    assert_eq!(payload["result"], "user: ada (4)");
+
+    // A missing extension answers with the extractor error.
+    let request: jsonrpsee_types::Request<'static> = AuthRequest::Whoami {
+        prefix: "user: ".to_string(),
+    }
+    .into();
+    let response =
+        ServiceExt::<jsonrpsee_types::Request<'static>>::oneshot(service.clone(), request)
+            .await
+            .unwrap();
+    assert!(matches!(
+        response.payload,
+        jsonrpsee_types::ResponsePayload::Error(ref err)
+            if err.code() == jsonrpsee_types::ErrorCode::InternalError.code()
+    ));
+
+    // Client methods only take the wire parameters.
+    let client = tower::ServiceBuilder::new()
+        .map_request(|mut req: jsonrpsee_types::Request<'static>| {
+            req.extensions.insert("grace".to_string());
+            req
+        })
+        .service(service);
+    let result = AuthClient::<jsonrpsee_types::Request<'static>>::whoami(&client, "user: ".into())
+        .await
+        .unwrap();
+    assert!(result.starts_with("user: grace"));
+}
+
+#[rpc(server, namespace = "debug")]
+pub trait Debug {
+    #[method(name = "traceBlock", with_extensions)]
+    async fn trace_block(
+        &self,
+        #[argument(extract)] cancel: tower_json_rpc::cancel::CancellationToken,
+    ) -> Result<(), ErrorObjectOwned>;
+}
+
+struct DebugImpl(
+    std::sync::Mutex<
+        Option<futures_channel::oneshot::Sender<tower_json_rpc::cancel::CancellationToken>>,
+    >,
+);
+
+impl Debug for DebugImpl {
+    async fn trace_block(
+        &self,
+        cancel: tower_json_rpc::cancel::CancellationToken,
+    ) -> Result<(), ErrorObjectOwned> {
+        let sender = self.0.lock().unwrap().take().unwrap();
+        sender.send(cancel).unwrap();
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn test_dropped_calls_cancel_the_handler_token() {
+    use tower::{Layer, ServiceExt};
+    use tower_json_rpc::router::MethodNotFound;
+
+    let (sender, receiver) = futures_channel::oneshot::channel();
+    let service =
+        DebugServerLayer::new(DebugImpl(std::sync::Mutex::new(Some(sender)))).layer(MethodNotFound);
+
+    // The generated service is the outermost layer, without a `JsonRpcServer` around it.
+    let request: jsonrpsee_types::Request<'static> = DebugRequest::TraceBlock {}.into();
+    let call = tokio::spawn(ServiceExt::<jsonrpsee_types::Request<'static>>::oneshot(
+        service, request,
+    ));
+    let token = receiver.await.unwrap();
+    assert!(!token.is_cancelled());
+
+    call.abort();
+    let _ = call.await;
+    assert!(token.is_cancelled());
+}
+
+#[tokio::test]
+async fn test_router_dispatches_generated_services() {
+    use tower::{Layer, ServiceExt};
+    use tower_json_rpc::router::{DuplicateMethod, MethodNotFound, RpcMethods, RpcRouter};
+
+    type SayService = SayServerService<MethodNotFound, SayImpl>;
+    assert_eq!(
+        <SayService as RpcMethods>::METHODS,
+        &["say_hello", "say_hello_alias", "say_goodbye"]
+    );
+
+    let router = RpcRouter::builder()
+        .service(SayServerLayer::new(SayImpl).layer(MethodNotFound))
+        .service(LegacyServerLayer::new(LegacyImpl).layer(MethodNotFound))
+        .build()
+        .unwrap();
+
+    let params = serde_json::value::to_raw_value(&vec![serde_json::json!("Ada")]).unwrap();
+    let request = jsonrpsee_types::Request::owned(
+        "say_goodbye".to_string(),
+        Some(params),
+        jsonrpsee_types::Id::Number(1),
+    );
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert!(matches!(
+        response.payload,
+        jsonrpsee_types::ResponsePayload::Success(value) if *value == "Goodbye, Ada!"
+    ));
+
+    let request = jsonrpsee_types::Request::owned(
+        "legacy_hello".to_string(),
+        None,
+        jsonrpsee_types::Id::Number(2),
+    );
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert!(matches!(
+        response.payload,
+        jsonrpsee_types::ResponsePayload::Success(value) if *value == "Hello!"
+    ));
+
+    let request = jsonrpsee_types::Request::owned(
+        "admin_stop".to_string(),
+        None,
+        jsonrpsee_types::Id::Number(3),
+    );
+    let response = router.oneshot(request).await.unwrap();
+    assert!(matches!(
+        response.payload,
+        jsonrpsee_types::ResponsePayload::Error(err)
+            if err.code() == jsonrpsee_types::ErrorCode::MethodNotFound.code()
+    ));
+
+    let duplicate = RpcRouter::builder()
+        .service(SayServerLayer::new(SayImpl).layer(MethodNotFound))
+        .route(["say_goodbye"], MethodNotFound)
+        .build()
+        .err();
+    assert_eq!(duplicate, Some(DuplicateMethod("say_goodbye".to_string())));
+}
+
+#[tokio::test]
+async fn test_router_mounts_service_under_namespaces() {
+    use tower::{Layer, ServiceExt};
+    use tower_json_rpc::namespace::NamespaceLayer;
+    use tower_json_rpc::router::{MethodNotFound, RpcRouter};
+
+    let service = SayServerLayer::new(SayImpl).layer(MethodNotFound);
+    let router = RpcRouter::builder()
+        .namespaced(
+            NamespaceLayer::new().strip("eth", "_").add("say", "_"),
+            service.clone(),
+        )
+        .namespaced(
+            NamespaceLayer::new().strip("debug", "_").add("say", "_"),
+            service,
+        )
+        .build()
+        .unwrap();
+
+    let mut methods: Vec<_> = router.methods().collect();
+    methods.sort();
+    assert_eq!(
+        methods,
+        [
+            "debug_goodbye",
+            "debug_hello",
+            "debug_hello_alias",
+            "eth_goodbye",
+            "eth_hello",
+            "eth_hello_alias"
+        ]
+    );
+
+    for method in ["eth_goodbye", "debug_goodbye"] {
+        let params = serde_json::value::to_raw_value(&vec![serde_json::json!("Ada")]).unwrap();
+        let request = jsonrpsee_types::Request::owned(
+            method.to_string(),
+            Some(params),
+            jsonrpsee_types::Id::Number(1),
+        );
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert!(matches!(
+            response.payload,
+            jsonrpsee_types::ResponsePayload::Success(value) if *value == "Goodbye, Ada!"
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_server_builder_method_layers() {
+    use tower::util::{MapRequestLayer, MapResultLayer};
+    use tower::{BoxError, Layer, ServiceExt};
+    use tower_json_rpc::method::MethodCall;
+    use tower_json_rpc::router::MethodNotFound;
+
+    let service = SayServerLayer::builder(SayImpl)
+        .say_hello_layer(MapRequestLayer::new(|mut call: MethodCall<(bool,)>| {
+            call.params.0 = !call.params.0;
+            call
+        }))
+        .say_goodbye_layer(MapResultLayer::new(|_: Result<String, BoxError>| {
+            Err::<String, BoxError>(ErrorObjectOwned::owned(-32000, "busy", None::<()>).into())
+        }))
+        .build()
+        .layer(MethodNotFound);
+
+    let request: jsonrpsee_types::Request<'static> = SayRequest::SayHello { param_0: true }.into();
+    let response = service.clone().oneshot(request).await.unwrap();
+    assert!(matches!(
+        response.payload,
+        jsonrpsee_types::ResponsePayload::Success(value) if *value == "Hi!"
+    ));
+
+    let request: jsonrpsee_types::Request<'static> = SayRequest::SayGoodbye {
+        name: "Ada".to_string(),
+    }
+    .into();
+    let response = service.oneshot(request).await.unwrap();
+    assert!(matches!(
+        response.payload,
+        jsonrpsee_types::ResponsePayload::Error(err) if err.code() == -32000
+    ));
+}
+
+fn range_cost(params: &jsonrpsee_types::Params<'_>) -> u64 {
+    let (from, to): (u64, u64) = params.parse().unwrap_or_default();
+    to.saturating_sub(from) + 1
+}
+
+#[rpc(
+    server,
+    namespace = "node",
+    timeout = "1s",
+    requires = "admin",
+    rate_limit = "100/s",
+    cost = 1
+)]
+pub trait Node {
+    #[method(name = "sleep", timeout = "20ms")]
+    async fn sleep(&self, millis: u64) -> Result<u64, ErrorObjectOwned>;
+
+    #[method(name = "tick", timeout = "500us")]
+    async fn tick(&self) -> Result<u64, ErrorObjectOwned>;
+
+    #[method(name = "status", aliases = ["node_health"], requires = "viewer", rate_limit = "1/m")]
+    async fn status(&self) -> Result<String, ErrorObjectOwned>;
+
+    #[method(name = "range", cost = range_cost)]
+    async fn range(&self, from: u64, to: u64) -> Result<u64, ErrorObjectOwned>;
+
+    #[method(name = "latest", cost = 5)]
+    async fn latest(&self) -> Result<u64, ErrorObjectOwned>;
+
+    #[method(name = "chainId", cacheable, ttl = "1h")]
+    async fn chain_id(&self) -> Result<u64, ErrorObjectOwned>;
+
+    #[method(name = "height")]
+    async fn height(&self) -> Result<u64, ErrorObjectOwned>;
+}
+
+#[derive(Default)]
+struct NodeImpl(std::sync::atomic::AtomicU64);
+
+impl Node for NodeImpl {
+    async fn sleep(&self, millis: u64) -> Result<u64, ErrorObjectOwned> {
+        tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+        Ok(millis)
+    }
+
+    async fn tick(&self) -> Result<u64, ErrorObjectOwned> {
+        Ok(0)
+    }
+
+    async fn status(&self) -> Result<String, ErrorObjectOwned> {
+        Ok("running".to_string())
+    }
+
+    async fn range(&self, from: u64, to: u64) -> Result<u64, ErrorObjectOwned> {
+        Ok(to - from)
+    }
+
+    async fn latest(&self) -> Result<u64, ErrorObjectOwned> {
+        Ok(10)
+    }
+
+    async fn chain_id(&self) -> Result<u64, ErrorObjectOwned> {
+        Ok(self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+    }
+
+    async fn height(&self) -> Result<u64, ErrorObjectOwned> {
+        Ok(self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+    }
+}
+
+#[test]
+fn test_method_attributes() {
+    use std::time::Duration;
+    use tower_json_rpc::cost::Cost;
+    use tower_json_rpc::method::MethodInfo;
+    use tower_json_rpc::rate_limit::RateLimit;
+    use tower_json_rpc::router::{MethodNotFound, RpcMethods};
+
+    // Methods inherit the trait-level attributes they don't declare.
+    let method = |name| {
+        MethodInfo::new(name)
+            .with_timeout(Duration::from_secs(1))
+            .with_requires("admin")
+            .with_rate_limit(RateLimit::new(100, Duration::from_secs(1)))
+            .with_cost(Cost::Fixed(1))
+    };
+    assert_eq!(
+        <NodeServerService<MethodNotFound, NodeImpl> as RpcMethods>::INFO,
+        [
+            method("node_sleep").with_timeout(Duration::from_millis(20)),
+            method("node_tick").with_timeout(Duration::from_micros(500)),
+            method("node_status")
+                .with_aliases(&["node_health"])
+                .with_requires("viewer")
+                .with_rate_limit(RateLimit::new(1, Duration::from_secs(60))),
+            method("node_range").with_cost(Cost::Dynamic(range_cost)),
+            method("node_latest").with_cost(Cost::Fixed(5)),
+            method("node_chainId")
+                .with_cacheable()
+                .with_ttl(Duration::from_secs(3600)),
+            method("node_height"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_method_attribute_layers() {
+    use tower::util::BoxCloneService;
+    use tower::{Layer, ServiceExt};
+    use tower_json_rpc::auth::{Principal, RpcAuthLayer, UNAUTHORIZED_CODE};
+    use tower_json_rpc::cache::RpcCacheLayer;
+    use tower_json_rpc::cost::{MemoryLedger, QUOTA_EXCEEDED_CODE, QuotaLayer};
+    use tower_json_rpc::error::JsonRpcError;
+    use tower_json_rpc::rate_limit::{JsonRpcRateLimitLayer, RATE_LIMITED_CODE};
+    use tower_json_rpc::router::{MethodNotFound, RpcMethods};
+    use tower_json_rpc::timeout::{RpcTimeoutLayer, TIMEOUT_CODE};
+
+    type Request = jsonrpsee_types::Request<'static>;
+    type Service = BoxCloneService<
+        Request,
+        jsonrpsee_types::Response<'static, serde_json::Value>,
+        JsonRpcError,
+    >;
+    let info = <NodeServerService<MethodNotFound, NodeImpl> as RpcMethods>::INFO;
+    let node = || NodeServerLayer::new(NodeImpl::default()).layer(MethodNotFound);
+
+    let timeout = Service::new(node());
+    let timeout_error = Service::new(
+        NodeServerLayer::builder(NodeImpl::default())
+            .timeout_error(ErrorObjectOwned::owned(-32050, "too slow", None::<()>))
+            .build()
+            .layer(MethodNotFound),
+    );
+    let auth = Service::new(RpcAuthLayer::new().methods(info).layer(node()));
+    let rate_limit = Service::new(JsonRpcRateLimitLayer::new().methods(info).layer(node()));
+    let ledger = MemoryLedger::new().with_default_quota(20);
+    let cost = Service::new(QuotaLayer::new(ledger.clone()).methods(info).layer(node()));
+    let cache = Service::new(RpcCacheLayer::new().methods(info).layer(node()));
+    // The declared timeouts can also be applied outside of the generated service.
+    let _ = RpcTimeoutLayer::new().methods(info).layer(MethodNotFound);
+
+    let alias = || {
+        Request::owned(
+            "node_health".to_string(),
+            None,
+            jsonrpsee_types::Id::Number(0),
+        )
+    };
+    let calls: Vec<(&Service, Request, Result<serde_json::Value, i32>)> = vec![
+        (
+            &timeout,
+            NodeRequest::Sleep { millis: 1 }.into(),
+            Ok(1.into()),
+        ),
+        (
+            &timeout,
+            NodeRequest::Sleep { millis: 200 }.into(),
+            Err(TIMEOUT_CODE),
+        ),
+        (
+            &timeout_error,
+            NodeRequest::Sleep { millis: 200 }.into(),
+            Err(-32050),
+        ),
+        (&auth, NodeRequest::Status {}.into(), Ok("running".into())),
+        (&auth, NodeRequest::Latest {}.into(), Err(UNAUTHORIZED_CODE)),
+        (
+            &rate_limit,
+            NodeRequest::Status {}.into(),
+            Ok("running".into()),
+        ),
+        // Aliases share the quota of the method.
+        (&rate_limit, alias(), Err(RATE_LIMITED_CODE)),
+        (
+            &cost,
+            NodeRequest::Range { from: 1, to: 10 }.into(),
+            Ok(9.into()),
+        ),
+        (&cost, NodeRequest::Latest {}.into(), Ok(10.into())),
+        (&cost, NodeRequest::Latest {}.into(), Ok(10.into())),
+        (
+            &cost,
+            NodeRequest::Height {}.into(),
+            Err(QUOTA_EXCEEDED_CODE),
+        ),
+        (&cache, NodeRequest::ChainId {}.into(), Ok(0.into())),
+        (&cache, NodeRequest::ChainId {}.into(), Ok(0.into())),
+        (&cache, NodeRequest::Height {}.into(), Ok(1.into())),
+        (&cache, NodeRequest::Height {}.into(), Ok(2.into())),
+    ];
+
+    for (index, (service, mut request, expected)) in calls.into_iter().enumerate() {
+        let id = jsonrpsee_types::Id::Number(index as u64);
+        request.id = id.clone();
+        request
+            .extensions
+            .insert(Principal::new("ada").with_role("viewer"));
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.id, id, "call {index}");
+        let result = match response.payload {
+            jsonrpsee_types::ResponsePayload::Success(value) => Ok(value.into_owned()),
+            jsonrpsee_types::ResponsePayload::Error(err) => Err(err.code()),
+        };
+        assert_eq!(result, expected, "call {index}");
+    }
+    assert_eq!(ledger.used("ada"), 20);
+}
+
+/// A client transport answering every call with `payload`.
+fn answer(
+    payload: jsonrpsee_types::ResponsePayload<'static, serde_json::Value>,
+) -> tower::util::BoxCloneService<
+    jsonrpsee_types::Request<'static>,
+    jsonrpsee_types::Response<'static, serde_json::Value>,
+    std::convert::Infallible,
+> {
+    tower::util::BoxCloneService::new(tower::service_fn(
+        move |req: jsonrpsee_types::Request<'static>| {
+            std::future::ready(Ok(jsonrpsee_types::Response::new(payload.clone(), req.id)))
+        },
+    ))
+}
+
+#[tokio::test]
+async fn test_client_request_ids() {
+    use tower::Layer;
+    use tower_json_rpc::{
+        client::{JsonRpcClientLayer, RpcCall},
+        error::JsonRpcError,
+    };
+
+    // The client layer sends the calls with distinct ids.
+    let echo_id = tower::service_fn(|req: jsonrpsee_types::Request<'static>| async move {
+        Ok::<_, std::convert::Infallible>(
+            jsonrpsee_types::Response::<'static, serde_json::Value>::new(
+                jsonrpsee_types::ResponsePayload::success(serde_json::json!(format!(
+                    "{:?}",
+                    req.id
+                ))),
+                req.id,
+            ),
+        )
+    });
+    let client = JsonRpcClientLayer::<jsonrpsee_types::Request<'static>>::default().layer(echo_id);
+    let whoami = || AuthClient::<jsonrpsee_types::Request<'static>>::whoami(&client, String::new());
+    assert_ne!(whoami().await.unwrap(), whoami().await.unwrap());
+
+    // A response answering another request is rejected.
+    let client = tower::service_fn(|req: jsonrpsee_types::Request<'static>| async move {
+        let id = match req.id {
+            jsonrpsee_types::Id::Number(id) => jsonrpsee_types::Id::Number(id + 1),
+            id => id,
+        };
+        Ok::<_, std::convert::Infallible>(
+            jsonrpsee_types::Response::<'static, serde_json::Value>::new(
+                jsonrpsee_types::ResponsePayload::success(serde_json::json!("ada")),
+                id,
+            ),
+        )
+    });
+    let err = AuthClient::<jsonrpsee_types::Request<'static>>::whoami(&client, "user: ".into())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, JsonRpcError::IdMismatch { .. }));
+
+    let err = client
+        .rpc_call(AuthRequest::Whoami {
+            prefix: String::new(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, JsonRpcError::IdMismatch { .. }));
+}
+
+#[tokio::test]
+async fn test_client_rpc_errors() {
+    use tower_json_rpc::client::RpcCall;
+
+    let client = answer(jsonrpsee_types::ResponsePayload::error(
+        ErrorObjectOwned::owned(3, "execution reverted", Some("0x08c379a0")),
+    ));
+
+    let err = AuthClient::<jsonrpsee_types::Request<'static>>::whoami(&client, "user: ".into())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(3));
+    assert_eq!(err.message(), Some("execution reverted"));
+    assert_eq!(err.data::<String>().unwrap().unwrap(), "0x08c379a0");
+
+    let err = client
+        .rpc_call(AuthRequest::Whoami {
+            prefix: String::new(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.rpc_error().map(|err| err.code()), Some(3));
+}
+
+#[rpc(client, namespace = "stats")]
+pub trait Stats {
+    #[method(name = "pending")]
+    async fn pending(&self) -> Result<Option<u64>, ErrorObjectOwned>;
+
+    #[method(name = "count")]
+    async fn count(&self) -> Result<u64, ErrorObjectOwned>;
+}
+
+#[tokio::test]
+async fn test_rpc_call_decodes_the_called_method() {
+    use tower_json_rpc::client::RpcCall;
+
+    let client = answer(jsonrpsee_types::ResponsePayload::success(5.into()));
+
+    // `5` is also a valid `Option<u64>`, the result of the first method.
+    let response = client.rpc_call(StatsRequest::Count {}).await.unwrap();
+    assert!(matches!(response, StatsResponse::Count(5)));
+    let response = client.rpc_call(StatsRequest::Pending {}).await.unwrap();
+    assert!(matches!(response, StatsResponse::Pending(Some(5))));
+}
+
+#[tokio::test]
+async fn test_client_batches() {
+    use tower_json_rpc::client::{Batch, RpcBatch};
+
+    // Answers the array of a batch in reverse order.
+    let client = tower::service_fn(
+        |requests: Vec<jsonrpsee_types::Request<'static>>| async move {
+            let responses = requests.into_iter().rev().map(|req| {
+                let result = match req.method.as_ref() {
+                    "stats_count" => serde_json::json!(5),
+                    "stats_pending" => serde_json::Value::Null,
+                    _ => serde_json::json!("user: ada"),
+                };
+                jsonrpsee_types::Response::<'static, serde_json::Value>::new(
+                    jsonrpsee_types::ResponsePayload::success(result),
+                    req.id,
+                )
+            });
+            Ok::<_, std::convert::Infallible>(responses.collect::<Vec<_>>())
+        },
+    );
+
+    // Batch methods and typed requests are queued when called, and give typed results.
+    let batch = Batch::new(client);
+    let whoami = batch.whoami("user: ".into());
+    let count = batch.call(StatsRequest::Count {});
+    batch.send().await.unwrap();
+    assert_eq!(whoami.await.unwrap(), "user: ada");
+    assert!(matches!(count.await.unwrap(), StatsResponse::Count(5)));
+
+    // Calls queued once the batch was sent fail.
+    assert!(batch.call(StatsRequest::Count {}).await.is_err());
+
+    let results = Batch::new(client)
+        .send_all([StatsRequest::Pending {}, StatsRequest::Count {}])
+        .await
+        .unwrap();
+    assert!(matches!(results[0], Ok(StatsResponse::Pending(None))));
+    assert!(matches!(results[1], Ok(StatsResponse::Count(5))));
+
+    // A client taking single requests has no array to send the calls in.
+    let single = answer(jsonrpsee_types::ResponsePayload::success(5.into()));
+    let batch = single.batch();
+    let count = batch.call(StatsRequest::Count {});
+    assert!(batch.send().await.is_err());
+    assert!(count.await.is_err());
+}
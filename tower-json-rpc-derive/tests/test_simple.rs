@@ -15,3 +15,142 @@ fn test_macro_expansion() {
     // If the macro expanded correctly, these types should exist
     let _request = TestRpcRequest::Hello {};
 }
+
+mod facade {
+    pub use tower_json_rpc as rpc;
+}
+
+#[test]
+fn test_crate_path_override() {
+    // Generated code only refers to the crate through the given path.
+    #[rpc(server, client, namespace = "facade", crate = "crate::facade::rpc")]
+    pub trait FacadeRpc {
+        #[method(name = "hello")]
+        async fn hello(&self, name: String) -> Result<String, jsonrpsee_types::ErrorObjectOwned>;
+    }
+
+    let request: jsonrpsee_types::Request<'static> = FacadeRpcRequest::Hello {
+        name: "Ada".to_string(),
+    }
+    .into();
+    assert_eq!(request.method, "facade_hello");
+}
+
+#[test]
+fn test_variant_names() {
+    // Variants are named after the Rust method, so any RPC name is accepted.
+    #[rpc(server, client)]
+    pub trait Chain {
+        #[method(name = "get.block")]
+        async fn get_block(&self, number: u64) -> Result<u64, jsonrpsee_types::ErrorObjectOwned>;
+
+        #[method(name = "2fa_check", variant = "TwoFactorCheck")]
+        fn check(&self) -> Result<bool, jsonrpsee_types::ErrorObjectOwned>;
+    }
+
+    let request: jsonrpsee_types::Request<'static> = ChainRequest::GetBlock { number: 1 }.into();
+    assert_eq!(request.method, "get.block");
+
+    let request: jsonrpsee_types::Request<'static> = ChainRequest::TwoFactorCheck {}.into();
+    assert!(matches!(
+        ChainRequest::try_from(request),
+        Ok(ChainRequest::TwoFactorCheck {})
+    ));
+}
+
+#[test]
+fn test_serde_round_trip() {
+    #[rpc(server, client, serde, namespace = "store")]
+    pub trait Store {
+        #[method(name = "put", param_kind = map, aliases = ["store_set"])]
+        fn put(
+            &self,
+            #[argument(rename = "k")] key: String,
+            value: u64,
+        ) -> Result<bool, jsonrpsee_types::ErrorObjectOwned>;
+
+        #[method(name = "get")]
+        fn get(&self, key: String) -> Result<Option<u64>, jsonrpsee_types::ErrorObjectOwned>;
+
+        #[method(name = "clear")]
+        fn clear(&self) -> Result<(), jsonrpsee_types::ErrorObjectOwned>;
+    }
+
+    let put = StoreRequest::Put {
+        key: "a".to_string(),
+        value: 1,
+    };
+    let json = serde_json::to_value(&put).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "method": "store_put", "params": { "k": "a", "value": 1 } })
+    );
+    assert!(matches!(
+        serde_json::from_value(json).unwrap(),
+        StoreRequest::Put { key, value: 1 } if key == "a"
+    ));
+
+    let get = serde_json::json!({ "method": "store_get", "params": ["b"] });
+    assert!(matches!(
+        serde_json::from_value(get).unwrap(),
+        StoreRequest::Get { key } if key == "b"
+    ));
+
+    let json = serde_json::to_value(StoreRequest::Clear {}).unwrap();
+    assert_eq!(json, serde_json::json!({ "method": "store_clear" }));
+
+    let alias = serde_json::json!({ "method": "store_set", "params": { "k": "c", "value": 3 } });
+    assert!(matches!(
+        serde_json::from_value(alias).unwrap(),
+        StoreRequest::Put { value: 3, .. }
+    ));
+
+    let unknown = serde_json::json!({ "method": "store_drop" });
+    assert!(serde_json::from_value::<StoreRequest>(unknown).is_err());
+
+    let response = serde_json::to_value(StoreResponse::Get(Some(4))).unwrap();
+    assert_eq!(
+        response,
+        serde_json::json!({ "method": "store_get", "result": 4 })
+    );
+    assert!(matches!(
+        serde_json::from_value(response).unwrap(),
+        StoreResponse::Get(Some(4))
+    ));
+
+    // Formats that aren't self-describing read the params in the order of the method.
+    let bytes = bincode::serialize(&put).unwrap();
+    assert!(matches!(
+        bincode::deserialize(&bytes).unwrap(),
+        StoreRequest::Put { key, value: 1 } if key == "a"
+    ));
+    let bytes = bincode::serialize(&StoreRequest::Clear {}).unwrap();
+    assert!(matches!(
+        bincode::deserialize(&bytes).unwrap(),
+        StoreRequest::Clear {}
+    ));
+    let bytes = bincode::serialize(&StoreResponse::Get(Some(4))).unwrap();
+    assert!(matches!(
+        bincode::deserialize(&bytes).unwrap(),
+        StoreResponse::Get(Some(4))
+    ));
+}
+
+#[test]
+fn test_serde_client_only() {
+    #[rpc(client, serde)]
+    pub trait Remote {
+        #[method(name = "echo")]
+        fn echo(&self, message: String) -> Result<String, jsonrpsee_types::ErrorObjectOwned>;
+    }
+
+    let request = RemoteRequest::Echo {
+        message: "hi".to_string(),
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(json, r#"{"method":"echo","params":["hi"]}"#);
+    assert!(matches!(
+        serde_json::from_str(&json).unwrap(),
+        RemoteRequest::Echo { message } if message == "hi"
+    ));
+}
@@ -0,0 +1,12 @@
+#[test]
+fn ui_incorrect() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/incorrect/rpc/rpc_deprecated_method.rs");
+    t.compile_fail("tests/ui/incorrect/rpc/rpc_deprecated_invalid_date.rs");
+    t.compile_fail("tests/ui/incorrect/rpc/rpc_name_conflict.rs");
+    t.compile_fail("tests/ui/incorrect/rpc/rpc_conflicting_alias.rs");
+    t.compile_fail("tests/ui/incorrect/rpc/rpc_variant_conflict.rs");
+    t.compile_fail("tests/ui/incorrect/method/method_invalid_variant.rs");
+    t.compile_fail("tests/ui/incorrect/method/method_invalid_rate_limit.rs");
+    t.compile_fail("tests/ui/incorrect/method/method_ttl_without_cacheable.rs");
+}
@@ -0,0 +1,9 @@
+use tower_json_rpc_derive::rpc;
+
+#[rpc(client, server)]
+pub trait InvalidRateLimit {
+	#[method(name = "foo", rate_limit = "10 per second")]
+	async fn foo(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
+}
+
+fn main() {}
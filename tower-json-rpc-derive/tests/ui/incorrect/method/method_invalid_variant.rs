@@ -0,0 +1,9 @@
+use tower_json_rpc_derive::rpc;
+
+#[rpc(client, server)]
+pub trait InvalidVariant {
+	#[method(name = "foo", variant = "2Foo")]
+	async fn foo(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
+}
+
+fn main() {}
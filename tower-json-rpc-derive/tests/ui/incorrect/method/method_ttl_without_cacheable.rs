@@ -0,0 +1,9 @@
+use tower_json_rpc_derive::rpc;
+
+#[rpc(client, server)]
+pub trait TtlWithoutCacheable {
+	#[method(name = "foo", ttl = "30s")]
+	async fn foo(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
+}
+
+fn main() {}
@@ -1,9 +1,9 @@
-use jsonrpsee::proc_macros::rpc;
-use jsonrpsee::core::RpcResult;
+use tower_json_rpc_derive::rpc;
+
 #[rpc(client, server)]
 pub trait DuplicatedAlias {
 	#[method(name = "foo", aliases = ["foo_dup", "foo_dup"])]
-	async fn async_method(&self) -> RpcResult<u8>;
+	async fn async_method(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
 }
 
 fn main() {}
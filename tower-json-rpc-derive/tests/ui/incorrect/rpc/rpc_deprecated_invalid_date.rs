@@ -0,0 +1,10 @@
+use tower_json_rpc_derive::rpc;
+
+#[rpc(client, server)]
+pub trait InvalidDate {
+	#[deprecated(since = "2024-02-31")]
+	#[method(name = "foo")]
+	async fn foo(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
+}
+
+fn main() {}
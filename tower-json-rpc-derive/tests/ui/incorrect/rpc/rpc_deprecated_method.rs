@@ -2,65 +2,56 @@
 
 // Treat warnings as errors to fail the build.
 #![deny(warnings)]
+#![allow(async_fn_in_trait)]
 
-use std::net::SocketAddr;
-
-use jsonrpsee::core::{async_trait, RpcResult};
-use jsonrpsee::proc_macros::rpc;
-use jsonrpsee::server::ServerBuilder;
-use jsonrpsee::ws_client::*;
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Request, Response, ResponsePayload};
+use tower::Layer;
+use tower_json_rpc_derive::rpc;
 
 #[rpc(client, server)]
 pub trait Deprecated {
 	// Deprecated method that is called by the client.
 	#[deprecated(since = "0.5.0", note = "please use `new_method` instead")]
 	#[method(name = "foo")]
-	async fn async_method(&self) -> RpcResult<u8>;
+	async fn async_method(&self) -> Result<u8, ErrorObjectOwned>;
 
 	// Deprecated methods that are not called should not generate warnings.
 	#[deprecated(since = "0.5.0", note = "please use `new_method` instead")]
 	#[method(name = "foo_unused")]
-	async fn async_method_unused(&self) -> RpcResult<u8>;
+	async fn async_method_unused(&self) -> Result<u8, ErrorObjectOwned>;
 
 	// If the method is not marked as deprecated, should not generate warnings.
 	#[method(name = "bar")]
-	fn sync_method(&self) -> RpcResult<u8>;
+	fn sync_method(&self) -> Result<u8, ErrorObjectOwned>;
 }
 
 pub struct DeprecatedServerImpl;
 
-#[async_trait]
-impl DeprecatedServer for DeprecatedServerImpl {
-	async fn async_method(&self) -> RpcResult<u8> {
+impl Deprecated for DeprecatedServerImpl {
+	async fn async_method(&self) -> Result<u8, ErrorObjectOwned> {
 		Ok(16u8)
 	}
 
-	async fn async_method_unused(&self) -> RpcResult<u8> {
+	async fn async_method_unused(&self) -> Result<u8, ErrorObjectOwned> {
 		Ok(32u8)
 	}
 
-	fn sync_method(&self) -> RpcResult<u8> {
+	fn sync_method(&self) -> Result<u8, ErrorObjectOwned> {
 		Ok(64u8)
 	}
 }
 
-pub async fn server() -> SocketAddr {
-	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
-	let addr = server.local_addr().unwrap();
-
-	server.start(DeprecatedServerImpl.into_rpc());
-
-	addr
-}
-
 #[tokio::main]
 async fn main() {
-	let server_addr = server().await;
-	let server_url = format!("ws://{}", server_addr);
-	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let client = DeprecatedServerLayer::new(DeprecatedServerImpl).layer(tower::service_fn(|req: Request<'static>| async move {
+		Ok::<_, std::convert::Infallible>(Response::new(
+			ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::MethodNotFound)),
+			req.id,
+		))
+	}));
 
 	// Calling this method should generate an warning.
-	assert_eq!(client.async_method().await.unwrap(), 16);
+	assert_eq!(DeprecatedClient::<Request<'static>>::async_method(&client).await.unwrap(), 16);
 	// Note: `async_method_unused` is not called, and should not generate warnings.
-	assert_eq!(client.sync_method().await.unwrap(), 64);
+	assert_eq!(DeprecatedClient::<Request<'static>>::sync_method(&client).await.unwrap(), 64);
 }
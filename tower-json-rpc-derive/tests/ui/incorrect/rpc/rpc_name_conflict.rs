@@ -1,13 +1,13 @@
-use jsonrpsee::{proc_macros::rpc, core::RpcResult};
+use tower_json_rpc_derive::rpc;
 
 // Names must be unique.
 #[rpc(client, server)]
 pub trait MethodNameConflict {
 	#[method(name = "foo")]
-	async fn foo(&self) -> RpcResult<u8>;
+	async fn foo(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
 
 	#[method(name = "foo")]
-	async fn bar(&self) -> RpcResult<u8>;
+	async fn bar(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
 }
 
 fn main() {}
@@ -0,0 +1,14 @@
+use tower_json_rpc_derive::rpc;
+
+// Generated enum variants must be unique.
+#[rpc(client, server)]
+#[allow(non_snake_case)]
+pub trait VariantConflict {
+	#[method(name = "foo_bar")]
+	async fn foo_bar(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
+
+	#[method(name = "fooBar")]
+	async fn fooBar(&self) -> Result<u8, jsonrpsee_types::ErrorObjectOwned>;
+}
+
+fn main() {}
@@ -2,9 +2,13 @@
 
 use axum::{Router, http::StatusCode, routing::post_service};
 use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Request, Response, ResponsePayload};
+use std::time::Duration;
 use tower::{ServiceBuilder, service_fn};
 use tower_http::trace::TraceLayer;
+
 use tower_json_rpc::error::JsonRpcError;
+use tower_json_rpc::server::JsonRpcLayer;
+use tower_json_rpc::shutdown::ShutdownHandle;
 use tower_json_rpc_derive::rpc;
 
 #[rpc(server, namespace = "say")]
@@ -32,8 +36,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ))
     });
 
+    let shutdown = ShutdownHandle::new();
     let rpc = ServiceBuilder::new()
         .layer(TraceLayer::new_for_http())
+        .layer(JsonRpcLayer)
+        .layer(shutdown.layer())
         .layer(SayServerLayer::new(handler))
         .service(fallback);
 
@@ -45,7 +52,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            tokio::signal::ctrl_c().await.ok();
+            // Refuse new calls and give the ones in flight some time to finish.
+            shutdown.shutdown(Duration::from_secs(10)).await;
+        })
+        .await?;
 
     Ok(())
 }
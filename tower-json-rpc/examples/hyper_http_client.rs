@@ -1,10 +1,8 @@
 #![allow(async_fn_in_trait)]
 
-use http_body_util::Full;
-use hyper::body::Bytes;
-use hyper_util::client::legacy::Client;
-use hyper_util::rt::TokioExecutor;
-use tower_json_rpc::client::RpcCall;
+use std::time::Duration;
+
+use tower_json_rpc::client::{HttpTransport, RpcCall};
 use tower_json_rpc::error::JsonRpcError;
 use tower_json_rpc::types::Request;
 use tower_json_rpc_derive::rpc;
@@ -16,10 +14,13 @@ pub trait Say {
 
 #[tokio::main]
 async fn main() -> Result<(), JsonRpcError> {
-    let client: Client<_, Full<Bytes>> = Client::builder(TokioExecutor::new()).build_http();
+    let client = HttpTransport::builder("http://127.0.0.1:3000/rpc")
+        .user_agent("hyper_http_client")
+        .timeout(Duration::from_secs(10))
+        .build()?;
 
     // The SayClient trait is automatically generated by the #[rpc(client)] macro.
-    // It extends any Service<Request<Full<Bytes>>> with RPC methods.
+    // It extends any Service<Request<'static>> with RPC methods.
     let result = client.hello("test".into()).await?;
     println!("Result from say_hello: {}", result);
 
@@ -0,0 +1,234 @@
+//! Role-based authorization of RPC methods.
+//!
+//! An authentication layer in front of the RPC service, such as an HTTP layer checking a token,
+//! inserts the [`Principal`] of the caller into the request extensions. [`RpcAuthLayer`] then
+//! checks its roles against the ones required by the methods, declared with
+//! `#[method(requires = "...")]`, and answers with an error before the handler runs.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::{Ready, ready},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::future::Either;
+use jsonrpsee_types::{ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::{Layer, Service};
+
+use crate::{
+    extract::{FromRequestParts, RequestParts},
+    method::MethodInfo,
+};
+
+/// Error code of the default unauthorized error.
+pub const UNAUTHORIZED_CODE: i32 = -32001;
+
+/// The default error returned when the caller lacks a required role: `-32001 Unauthorized`.
+pub fn unauthorized_error() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(UNAUTHORIZED_CODE, "Unauthorized", None::<()>)
+}
+
+/// The authenticated caller of a request, read from the request extensions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Principal {
+    /// Identifier of the caller, such as a user name or an API key id.
+    pub id: String,
+    /// Roles granted to the caller.
+    pub roles: BTreeSet<String>,
+}
+
+impl Principal {
+    /// Creates a principal without any role.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            roles: BTreeSet::new(),
+        }
+    }
+
+    /// Grants `role` to the principal.
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.roles.insert(role.into());
+        self
+    }
+
+    /// Whether the principal was granted `role`.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.contains(role)
+    }
+}
+
+/// Extracts the caller, failing with [`unauthorized_error`] for anonymous requests.
+impl FromRequestParts for Principal {
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(unauthorized_error)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Requirements {
+    methods: HashMap<String, String>,
+    error: ErrorObjectOwned,
+}
+
+/// A layer that rejects calls to methods whose required role the caller doesn't have.
+///
+/// ```ignore
+/// let layer = RpcAuthLayer::new()
+///     .methods(<AdminService as RpcMethods>::INFO)
+///     .method("debug_traceCall", "debug");
+/// ```
+///
+/// Methods without a required role are called for every request, including anonymous ones.
+#[derive(Debug, Clone)]
+pub struct RpcAuthLayer {
+    requirements: Arc<Requirements>,
+}
+
+impl Default for RpcAuthLayer {
+    fn default() -> Self {
+        Self {
+            requirements: Arc::new(Requirements {
+                methods: HashMap::new(),
+                error: unauthorized_error(),
+            }),
+        }
+    }
+}
+
+impl RpcAuthLayer {
+    /// Creates a layer that doesn't require any role.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires `role` to call the method `name`.
+    pub fn method(mut self, name: impl Into<String>, role: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.requirements)
+            .methods
+            .insert(name.into(), role.into());
+        self
+    }
+
+    /// Requires the roles declared with `#[method(requires = "...")]`, including aliases.
+    pub fn methods(mut self, methods: &[MethodInfo]) -> Self {
+        let requirements = Arc::make_mut(&mut self.requirements);
+        for info in methods {
+            if let Some(role) = info.requires {
+                requirements.methods.extend(
+                    info.names()
+                        .map(|name| (name.to_string(), role.to_string())),
+                );
+            }
+        }
+        self
+    }
+
+    /// Sets the error returned to unauthorized callers, instead of [`unauthorized_error`].
+    pub fn error(mut self, error: ErrorObjectOwned) -> Self {
+        Arc::make_mut(&mut self.requirements).error = error;
+        self
+    }
+}
+
+impl<S> Layer<S> for RpcAuthLayer {
+    type Service = RpcAuth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcAuth {
+            inner,
+            requirements: self.requirements.clone(),
+        }
+    }
+}
+
+/// Service produced by [`RpcAuthLayer`].
+#[derive(Debug, Clone)]
+pub struct RpcAuth<S> {
+    inner: S,
+    requirements: Arc<Requirements>,
+}
+
+impl<S> Service<Request<'static>> for RpcAuth<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let authorized = match self.requirements.methods.get(request.method.as_ref()) {
+            Some(role) => request
+                .extensions
+                .get::<Principal>()
+                .is_some_and(|principal| principal.has_role(role)),
+            None => true,
+        };
+
+        if authorized {
+            Either::Left(self.inner.call(request))
+        } else {
+            Either::Right(ready(Ok(Response::new(
+                ResponsePayload::error(self.requirements.error.clone()),
+                request.id,
+            ))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee_types::{Id, ResponsePayload};
+    use tower::Layer;
+
+    use crate::{
+        auth::{Principal, RpcAuthLayer, UNAUTHORIZED_CODE},
+        test_util::{call, echo, request},
+    };
+
+    #[tokio::test]
+    async fn rejects_callers_without_the_role() {
+        let svc = RpcAuthLayer::new()
+            .method("admin_stop", "admin")
+            .layer(echo());
+
+        let call = |method: &str, principal: Option<Principal>| {
+            let mut request = request(method, 3);
+            if let Some(principal) = principal {
+                request.extensions.insert(principal);
+            }
+            call(&svc, request)
+        };
+
+        let response = call("admin_stop", None).await.unwrap();
+        assert_eq!(response.id, Id::Number(3));
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Error(err) if err.code() == UNAUTHORIZED_CODE
+        ));
+
+        let response = call("admin_stop", Some(Principal::new("bob").with_role("user")))
+            .await
+            .unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Error(_)));
+
+        let response = call("admin_stop", Some(Principal::new("ada").with_role("admin")))
+            .await
+            .unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+
+        let response = call("say_hello", None).await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+    }
+}
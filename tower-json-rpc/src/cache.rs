@@ -0,0 +1,391 @@
+//! Caching of the results of pure RPC methods.
+//!
+//! [`RpcCacheLayer`] keeps the successful results of the methods marked `#[method(cacheable)]`,
+//! keyed by method and params, and answers the identical calls from the cache until the
+//! result expires. Errors are never cached, and cached results are answered with the id of
+//! each request and the extensions of the first response, such as its [`Deprecation`].
+//!
+//! [`Deprecation`]: crate::server::Deprecation
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::ready,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::FutureExt;
+use http::Extensions;
+use jsonrpsee_types::{Request, Response, ResponsePayload};
+use serde_json::{Value, value::RawValue};
+use tower::{Layer, Service};
+
+use crate::{method::MethodInfo, server::BoxFuture, util::take_ready};
+
+/// The TTL of the cached methods without their own `ttl`.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5);
+
+/// The number of results kept by default.
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// The key of a call, so that params differing only in the order of the map keys or in
+/// whitespace share the same result.
+pub fn cache_key(method: &str, params: Option<&RawValue>) -> (String, String) {
+    let params = params
+        .and_then(|params| serde_json::from_str::<Value>(params.get()).ok())
+        .map(canonicalize)
+        .unwrap_or(Value::Null);
+    (method.to_string(), params.to_string())
+}
+
+/// Sorts the keys of the maps in `value`. serde_json keeps them sorted already, unless a crate
+/// in the build enables its `preserve_order` feature, which keeps them in the order they were
+/// written.
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map
+                .into_iter()
+                .map(|(key, value)| (key, canonicalize(value)))
+                .collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(entries.into_iter().collect())
+        }
+        value => value,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Policy {
+    default_ttl: Duration,
+    max_entries: usize,
+    /// TTLs by method name or alias, along with the name the results are cached under.
+    methods: HashMap<String, (String, Option<Duration>)>,
+}
+
+type Key = (String, String);
+
+/// When an entry expires, made unique by the order it was inserted in.
+type Expiry = (Instant, u64);
+
+#[derive(Debug)]
+struct Entry {
+    result: Value,
+    extensions: Extensions,
+    expires: Expiry,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<Key, Entry>,
+    /// The keys of the entries, closest to expiring first.
+    expiries: BTreeMap<Expiry, Key>,
+    inserted: u64,
+}
+
+impl Entries {
+    fn get(&mut self, key: &Key, now: Instant) -> Option<(Value, Extensions)> {
+        let entry = self.entries.get(key)?;
+        if entry.expires.0 > now {
+            return Some((entry.result.clone(), entry.extensions.clone()));
+        }
+        self.remove(key);
+        None
+    }
+
+    fn insert(
+        &mut self,
+        key: Key,
+        result: Value,
+        extensions: Extensions,
+        expires: Instant,
+        max_entries: usize,
+    ) {
+        if max_entries == 0 {
+            return;
+        }
+        self.remove(&key);
+        // Make room by dropping the results closest to expiring, which includes the expired ones.
+        while self.entries.len() >= max_entries {
+            let Some((_, oldest)) = self.expiries.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        self.inserted += 1;
+        let expires = (expires, self.inserted);
+        self.expiries.insert(expires, key.clone());
+        let entry = Entry {
+            result,
+            extensions,
+            expires,
+        };
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.expiries.remove(&entry.expires);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.expiries.clear();
+    }
+}
+
+/// A layer that caches the successful results of cacheable methods.
+///
+/// ```ignore
+/// let layer = RpcCacheLayer::new()
+///     .methods(<EthService as RpcMethods>::INFO)
+///     .method("eth_chainId", Duration::from_secs(3600))
+///     .max_entries(50_000);
+/// ```
+///
+/// Only the methods set with [`method`](Self::method) or declared `cacheable` are cached.
+/// Clones of the layer, and the services it creates, share the same cache.
+#[derive(Debug, Clone)]
+pub struct RpcCacheLayer {
+    policy: Arc<Policy>,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl Default for RpcCacheLayer {
+    fn default() -> Self {
+        Self {
+            policy: Arc::new(Policy {
+                default_ttl: DEFAULT_TTL,
+                max_entries: DEFAULT_MAX_ENTRIES,
+                methods: HashMap::new(),
+            }),
+            entries: Arc::default(),
+        }
+    }
+}
+
+impl RpcCacheLayer {
+    /// Creates a layer that doesn't cache any method.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the TTL of the cacheable methods without their own `ttl`, instead of
+    /// [`DEFAULT_TTL`].
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        Arc::make_mut(&mut self.policy).default_ttl = ttl;
+        self
+    }
+
+    /// Sets the number of results kept, instead of [`DEFAULT_MAX_ENTRIES`].
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        Arc::make_mut(&mut self.policy).max_entries = max_entries;
+        self
+    }
+
+    /// Caches the results of the method `name` for `ttl`.
+    pub fn method(mut self, name: impl Into<String>, ttl: Duration) -> Self {
+        let name = name.into();
+        Arc::make_mut(&mut self.policy)
+            .methods
+            .insert(name.clone(), (name, Some(ttl)));
+        self
+    }
+
+    /// Caches the methods declared with `#[method(cacheable)]`. Calls to the aliases of a
+    /// method share the results of the method.
+    pub fn methods(mut self, methods: &[MethodInfo]) -> Self {
+        let policy = Arc::make_mut(&mut self.policy);
+        for info in methods.iter().filter(|info| info.cacheable) {
+            policy.methods.extend(
+                info.names()
+                    .map(|name| (name.to_string(), (info.name.to_string(), info.ttl))),
+            );
+        }
+        self
+    }
+
+    /// Drops every cached result, such as when a new block invalidates them.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl<S> Layer<S> for RpcCacheLayer {
+    type Service = RpcCache<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcCache {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`RpcCacheLayer`].
+#[derive(Debug, Clone)]
+pub struct RpcCache<S> {
+    inner: S,
+    layer: RpcCacheLayer,
+}
+
+impl<S> Service<Request<'static>> for RpcCache<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let policy = &self.layer.policy;
+        let Some((method, ttl)) = policy.methods.get(request.method.as_ref()) else {
+            return Box::pin(self.inner.call(request));
+        };
+        let ttl = ttl.unwrap_or(policy.default_ttl);
+        let max_entries = policy.max_entries;
+        let key = cache_key(method, request.params.as_deref());
+
+        let cached = self.layer.entries.lock().unwrap().get(&key, Instant::now());
+        if let Some((result, extensions)) = cached {
+            let mut response = Response::new(ResponsePayload::success(result), request.id);
+            response.extensions = extensions;
+            return Box::pin(ready(Ok(response)));
+        }
+
+        let mut inner = take_ready(&mut self.inner);
+        let entries = self.layer.entries.clone();
+
+        Box::pin(inner.call(request).map(move |result| {
+            if let Ok(Response {
+                payload: ResponsePayload::Success(value),
+                extensions,
+                ..
+            }) = &result
+            {
+                let result = value.clone().into_owned();
+                let expires = Instant::now() + ttl;
+                entries.lock().unwrap().insert(
+                    key,
+                    result,
+                    extensions.clone(),
+                    expires,
+                    max_entries,
+                );
+            }
+            result
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::{Duration, Instant},
+    };
+
+    use http::Extensions;
+    use jsonrpsee_types::{ErrorObjectOwned, Id, Request, Response, ResponsePayload};
+    use serde_json::{Value, value::to_raw_value};
+    use tower::{Layer, service_fn};
+
+    use crate::{
+        cache::{Entries, RpcCacheLayer, cache_key},
+        test_util::call,
+    };
+
+    #[test]
+    fn full_caches_drop_the_results_closest_to_expiring() {
+        let now = Instant::now();
+        let mut entries = Entries::default();
+        let key = |method: &str| cache_key(method, None);
+        let insert = |entries: &mut Entries, method, secs| {
+            let expires = now + Duration::from_secs(secs);
+            entries.insert(key(method), Value::Null, Extensions::new(), expires, 2);
+        };
+
+        insert(&mut entries, "a", 30);
+        insert(&mut entries, "b", 10);
+        insert(&mut entries, "c", 20);
+        assert!(entries.get(&key("a"), now).is_some());
+        assert!(entries.get(&key("b"), now).is_none());
+        assert!(entries.get(&key("c"), now).is_some());
+
+        // Replacing a result doesn't make room for it.
+        insert(&mut entries, "c", 40);
+        assert!(entries.get(&key("a"), now).is_some());
+        assert!(entries.get(&key("c"), now).is_some());
+
+        assert!(
+            entries
+                .get(&key("a"), now + Duration::from_secs(30))
+                .is_none()
+        );
+        assert_eq!(entries.expiries.len(), 1);
+    }
+
+    #[test]
+    fn keys_ignore_the_order_of_map_keys() {
+        let a = to_raw_value(&serde_json::json!({ "a": 1, "b": [{ "c": 2, "d": 3 }] })).unwrap();
+        let b: Box<_> = serde_json::from_str(r#"{"b": [{"d": 3, "c": 2}], "a": 1}"#).unwrap();
+        assert_eq!(cache_key("m", Some(&a)), cache_key("m", Some(&b)));
+    }
+
+    #[tokio::test]
+    async fn caches_successful_results() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = calls.clone();
+        let svc = RpcCacheLayer::new()
+            .method("eth_getBlock", Duration::from_secs(60))
+            .layer(service_fn(move |req: Request<'static>| {
+                let count = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let payload = match req.params.as_deref().map(|params| params.get()) {
+                        Some("[0]") => ResponsePayload::error(ErrorObjectOwned::owned(
+                            -32000,
+                            "not found",
+                            None::<()>,
+                        )),
+                        _ => ResponsePayload::success(Value::from(count)),
+                    };
+                    let mut response = Response::new(payload, req.id);
+                    response.extensions.insert(count);
+                    Ok::<_, std::convert::Infallible>(response)
+                }
+            }));
+
+        let call = |params: Value, id: u64| {
+            let params = to_raw_value(&params).unwrap();
+            let request = Request::owned("eth_getBlock".to_string(), Some(params), Id::Number(id));
+            call(&svc, request)
+        };
+
+        for id in [1, 2] {
+            let response = call(serde_json::json!([1]), id).await.unwrap();
+            assert_eq!(response.id, Id::Number(id));
+            assert_eq!(response.extensions.get::<u64>(), Some(&0));
+            assert!(matches!(response.payload, ResponsePayload::Success(value) if *value == 0));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        for _ in 0..2 {
+            let response = call(serde_json::json!([0]), 3).await.unwrap();
+            assert!(matches!(response.payload, ResponsePayload::Error(_)));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,144 @@
+//! Cancellation of the calls whose client went away.
+//!
+//! [`JsonRpcServer`](crate::server::JsonRpcServer) and the generated `<Trait>ServerService`
+//! put a [`CancellationToken`] into the extensions of every request with [`DropGuard::arm`],
+//! and cancel it when the response future is dropped before completing, such as when hyper
+//! drops the in-flight calls of a closed connection. Dropping the future already stops async
+//! handlers at their next `.await`, so the token is for handlers doing long stretches of work
+//! between them, which can check it cooperatively:
+//!
+//! ```ignore
+//! #[method(name = "traceBlock", with_extensions)]
+//! async fn trace_block(
+//!     &self,
+//!     block: u64,
+//!     #[argument(extract)] cancel: CancellationToken,
+//! ) -> Result<Trace, ErrorObjectOwned>;
+//! ```
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use jsonrpsee_types::{ErrorObjectOwned, Request};
+use tokio::sync::Notify;
+
+use crate::extract::{FromRequestParts, RequestParts};
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// A signal that the result of a call is no longer awaited. Clones share the same signal.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token and wakes up the tasks waiting on [`cancelled`](Self::cancelled).
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Completes once the token is cancelled, to be raced with the work of a handler.
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // Register before checking, so a cancellation in between isn't missed.
+        notified.as_mut().enable();
+        if !self.is_cancelled() {
+            notified.await;
+        }
+    }
+
+    /// Returns a guard cancelling the token when dropped, unless it is disarmed first.
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard { token: Some(self) }
+    }
+}
+
+/// Extracts the token of the request, or a token that is never cancelled when the transport
+/// doesn't provide one.
+impl FromRequestParts for CancellationToken {
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        Ok(parts
+            .extensions
+            .get::<CancellationToken>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Cancels a [`CancellationToken`] when dropped. See [`CancellationToken::drop_guard`].
+#[derive(Debug)]
+pub struct DropGuard {
+    token: Option<CancellationToken>,
+}
+
+impl DropGuard {
+    /// Puts a token into the extensions of `request`, unless an outer layer already did, and
+    /// guards it. Servers arm the guard when a call starts and disarm it once it completes.
+    pub fn arm(request: &mut Request<'_>) -> Self {
+        let token = request
+            .extensions
+            .get::<CancellationToken>()
+            .cloned()
+            .unwrap_or_default();
+        request.extensions.insert(token.clone());
+        token.drop_guard()
+    }
+
+    /// Keeps the token from being cancelled, such as once the call completed.
+    pub fn disarm(mut self) -> CancellationToken {
+        self.token.take().expect("the token is only taken once")
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            token.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::cancel::CancellationToken;
+
+    #[tokio::test]
+    async fn guards_cancel_waiting_tasks() {
+        let token = CancellationToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+
+        token.clone().drop_guard().disarm();
+        assert!(!token.is_cancelled());
+
+        drop(token.clone().drop_guard());
+        assert!(token.is_cancelled());
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
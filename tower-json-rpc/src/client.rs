@@ -3,8 +3,11 @@ use hyper::body::{Bytes, Incoming};
 use jsonrpsee_types::{Request, Response};
 use serde_json::Value;
 use std::{
+    fmt,
     future::Future,
+    marker::PhantomData,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 use tower::{Layer, Service};
@@ -14,36 +17,71 @@ use crate::{
     server::{ServerRequest, ServerResponse},
 };
 
+mod batch;
+mod id;
+mod transport;
+
+pub use batch::{ArrayClient, Batch, BatchCall, RpcBatch};
+pub use id::{Counter, IdGenerator, Uuids, verify_id};
+
+use id::{ClientIds, SentId};
+pub use transport::{HttpBatchTransport, HttpTransport, HttpTransportBuilder};
+
 pub trait ClientRequest: Sized + Send + 'static {
     type Response: ClientResponse;
 
     fn from_json_rpc_request(
         request: Request<'static>,
     ) -> Pin<Box<dyn Future<Output = Result<Self, JsonRpcError>> + Send + 'static>>;
+
+    /// Converts a batch of requests into one transport request carrying their array. Fails by
+    /// default, for transports without an array body.
+    fn from_json_rpc_batch(
+        requests: Vec<Request<'static>>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self, JsonRpcError>> + Send + 'static>> {
+        let error = JsonRpcError::RequestProcessing(format!(
+            "the transport can't send a batch of {} requests in one array",
+            requests.len()
+        ));
+        Box::pin(std::future::ready(Err(error)))
+    }
+}
+
+fn http_request(body: Vec<u8>) -> Result<hyper::Request<Full<Bytes>>, JsonRpcError> {
+    hyper::Request::builder()
+        .method(hyper::Method::POST)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body)))
+        .map_err(|e| JsonRpcError::RequestProcessing(e.to_string()))
 }
 
 /// Implementation for hyper HTTP requests with `Full<Bytes>` body.
 /// This is the common case when using hyper_util's legacy Client.
+///
+/// The request has no URI, which the client service must set, such as with a layer.
+/// [`HttpTransport`] posts to an endpoint URL on its own.
 impl ClientRequest for hyper::Request<Full<Bytes>> {
     type Response = hyper::Response<Incoming>;
 
     fn from_json_rpc_request(
         request: Request<'static>,
     ) -> Pin<Box<dyn Future<Output = Result<Self, JsonRpcError>> + Send + 'static>> {
-        Box::pin(async move {
-            let body = serde_json::to_vec(&request)?;
-            let http_request = hyper::Request::builder()
-                .method(hyper::Method::POST)
-                .header(hyper::header::CONTENT_TYPE, "application/json")
-                .body(Full::new(Bytes::from(body)))
-                .map_err(|e| JsonRpcError::RequestProcessing(e.to_string()))?;
-            Ok(http_request)
-        })
+        Box::pin(async move { http_request(serde_json::to_vec(&request)?) })
+    }
+
+    /// Posts the batch in one JSON array.
+    fn from_json_rpc_batch(
+        requests: Vec<Request<'static>>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self, JsonRpcError>> + Send + 'static>> {
+        Box::pin(async move { http_request(serde_json::to_vec(&requests)?) })
     }
 }
 
 /// Implementation for direct JSON-RPC requests.
 /// This is used by WebSocket clients that work directly with JSON-RPC messages.
+///
+/// A request carries a single call, so batches fail. [`HttpTransport::batch`] posts them in
+/// one array instead.
 impl ClientRequest for Request<'static> {
     type Response = Response<'static, Value>;
 
@@ -54,12 +92,33 @@ impl ClientRequest for Request<'static> {
     }
 }
 
+/// The responses of a transport request of a batch.
+pub type BatchResponseFuture = Pin<
+    Box<dyn Future<Output = Result<Vec<Response<'static, Value>>, JsonRpcError>> + Send + 'static>,
+>;
+
 pub trait ClientResponse: Send + 'static {
     fn to_json_rpc_response(
         self,
     ) -> Pin<
         Box<dyn Future<Output = Result<Response<'static, Value>, JsonRpcError>> + Send + 'static>,
     >;
+
+    /// Converts the answer to a transport request of a batch. Defaults to a single response.
+    fn to_json_rpc_batch_response(self) -> BatchResponseFuture
+    where
+        Self: Sized,
+    {
+        let response = self.to_json_rpc_response();
+        Box::pin(async move { Ok(vec![response.await?]) })
+    }
+}
+
+async fn read_body(response: hyper::Response<Incoming>) -> Result<Bytes, JsonRpcError> {
+    let body = response.into_body().collect().await.map_err(|e| {
+        JsonRpcError::RequestProcessing(format!("Failed to read response body: {}", e))
+    })?;
+    Ok(body.to_bytes())
 }
 
 impl ClientResponse for hyper::Response<Incoming> {
@@ -69,14 +128,27 @@ impl ClientResponse for hyper::Response<Incoming> {
         Box<dyn Future<Output = Result<Response<'static, Value>, JsonRpcError>> + Send + 'static>,
     > {
         Box::pin(async move {
-            let body = self.into_body().collect().await.map_err(|e| {
-                JsonRpcError::RequestProcessing(format!("Failed to read response body: {}", e))
-            })?;
-            let bytes = body.to_bytes();
+            let bytes = read_body(self).await?;
             let response: Response<'_, Value> = serde_json::from_slice(&bytes)?;
             Ok(response.into_owned())
         })
     }
+
+    /// Reads the array answering a batch, or the single error refusing it.
+    fn to_json_rpc_batch_response(self) -> BatchResponseFuture {
+        Box::pin(async move { Ok(batch_responses(&read_body(self).await?)?) })
+    }
+}
+
+/// Parses the array answering a batch, or the single error refusing it.
+pub(crate) fn batch_responses(
+    body: &[u8],
+) -> Result<Vec<Response<'static, Value>>, serde_json::Error> {
+    let responses: Vec<Response<'_, Value>> = match serde_json::from_slice(body) {
+        Ok(responses) => responses,
+        Err(_) => vec![serde_json::from_slice(body)?],
+    };
+    Ok(responses.into_iter().map(Response::into_owned).collect())
 }
 
 /// Implementation for direct JSON-RPC responses.
@@ -97,6 +169,9 @@ impl ClientResponse for Response<'static, Value> {
 /// `Service<TransportReq>` where `TransportReq: ClientRequest`. The request
 /// enum type must implement `ServerRequest`.
 ///
+/// A response whose id isn't the id of the request is rejected with
+/// [`JsonRpcError::IdMismatch`].
+///
 /// # Example
 ///
 /// ```ignore
@@ -135,6 +210,8 @@ where
         let service = self.clone();
         Box::pin(async move {
             let json_request: Request<'static> = request.into_json_rpc_request().await?;
+            let id = json_request.id.clone();
+            let method = json_request.method.to_string();
             let client_request = TransportReq::from_json_rpc_request(json_request).await?;
             let mut service = service;
             crate::__private::futures_util::future::poll_fn(|cx| {
@@ -150,49 +227,93 @@ where
                 .map_err(Into::into)?;
             let response =
                 <TransportReq::Response as ClientResponse>::to_json_rpc_response(response).await?;
-            Req::Response::from_json_rpc_response(response).await
-            // match response.payload {
-            //     jsonrpsee_types::ResponsePayload::Success(value) => {
-            //         request.parse_response(value.into_owned())
-            //     }
-            //     jsonrpsee_types::ResponsePayload::Error(err) => {
-            //         Err(JsonRpcError::RequestProcessing(err.to_string()))
-            //     }
-            // }
+            let response = verify_id(&id, response)?;
+            Req::Response::from_method_response(&method, response).await
         })
     }
 }
 
 /// A layer that maps http requests to JSON-RPC requests.
-#[derive(Clone, Debug)]
+///
+/// The requests are sent with ids from the generator of the layer, a [`Counter`] shared by the
+/// services it creates unless replaced with [`ids`](Self::ids), and answered with the ids they
+/// were created with. Responses whose id isn't the id sent are rejected with
+/// [`JsonRpcError::IdMismatch`].
 pub struct JsonRpcClientLayer<Req> {
-    _req: std::marker::PhantomData<Req>,
+    ids: ClientIds,
+    _req: PhantomData<Req>,
+}
+
+impl<Req> JsonRpcClientLayer<Req> {
+    /// Sends the requests with ids from `ids`.
+    pub fn ids(mut self, ids: impl IdGenerator) -> Self {
+        self.ids = ClientIds(Arc::new(ids));
+        self
+    }
 }
 
 impl<Req> Default for JsonRpcClientLayer<Req> {
     fn default() -> Self {
         Self {
-            _req: std::marker::PhantomData,
+            ids: ClientIds::default(),
+            _req: PhantomData,
+        }
+    }
+}
+
+impl<Req> Clone for JsonRpcClientLayer<Req> {
+    fn clone(&self) -> Self {
+        Self {
+            ids: self.ids.clone(),
+            _req: PhantomData,
         }
     }
 }
 
+impl<Req> fmt::Debug for JsonRpcClientLayer<Req> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonRpcClientLayer")
+            .field("ids", &self.ids)
+            .finish()
+    }
+}
+
 impl<S, Req> Layer<S> for JsonRpcClientLayer<Req> {
     type Service = JsonRpcClient<S, Req>;
 
     fn layer(&self, inner: S) -> Self::Service {
         JsonRpcClient {
             inner,
-            _req: std::marker::PhantomData,
+            ids: self.ids.clone(),
+            _req: PhantomData,
         }
     }
 }
 
 /// Maps JSON-RPC requests to client requests
-#[derive(Debug, Clone)]
 pub struct JsonRpcClient<S, Req> {
     inner: S,
-    _req: std::marker::PhantomData<Req>,
+    ids: ClientIds,
+    _req: PhantomData<Req>,
+}
+
+impl<S: Clone, Req> Clone for JsonRpcClient<S, Req> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            ids: self.ids.clone(),
+            _req: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, Req> fmt::Debug for JsonRpcClient<S, Req> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonRpcClient")
+            .field("inner", &self.inner)
+            .field("ids", &self.ids)
+            .finish()
+    }
 }
 
 impl<S, Req> Service<Request<'static>> for JsonRpcClient<S, Req>
@@ -211,23 +332,31 @@ where
         self.inner.poll_ready(cx).map_err(Into::into)
     }
 
-    fn call(&mut self, request: Request<'static>) -> Self::Future {
+    fn call(&mut self, mut request: Request<'static>) -> Self::Future {
         let mut service = self.inner.clone();
+        let sent_id = SentId::replace(&self.ids, &mut request);
 
         Box::pin(async move {
             let client_request = Req::from_json_rpc_request(request).await?;
             let response = service.call(client_request).await.map_err(Into::into)?;
-            response.to_json_rpc_response().await
+            sent_id.restore(response.to_json_rpc_response().await?)
         })
     }
 }
 #[cfg(test)]
 mod tests {
     use jsonrpsee_types::{Id, Request, Response, ResponsePayload};
-    use std::{future::Future, pin::Pin};
-    use tower::{ServiceBuilder, ServiceExt, service_fn};
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+    use tower::{Layer, ServiceBuilder, ServiceExt, service_fn};
 
-    use crate::client::{ClientRequest, ClientResponse, JsonRpcClientLayer};
+    use crate::client::{ClientRequest, ClientResponse, Counter, JsonRpcClientLayer, RpcCall};
     use crate::error::JsonRpcError;
 
     #[derive(Clone)]
@@ -277,4 +406,51 @@ mod tests {
         let response = service.oneshot(request).await.unwrap();
         assert!(matches!(response.payload, ResponsePayload::Success(_)));
     }
+
+    #[tokio::test]
+    async fn client_layer_checks_response_ids() {
+        let echo = |offset: u64| {
+            service_fn(move |req: DummyRequest| async move {
+                let Id::Number(id) = req.0.id else {
+                    unreachable!("requests have numeric ids")
+                };
+                Ok::<_, std::convert::Infallible>(DummyResponse(Response::new(
+                    ResponsePayload::success(serde_json::json!(id)),
+                    Id::Number(id + offset),
+                )))
+            })
+        };
+        let layer = JsonRpcClientLayer::<DummyRequest>::default().ids(Counter::starting_at(100));
+        let request = || Request::owned("ping".to_string(), None, Id::Number(7));
+
+        // The request is sent with a generated id, and answered with its own.
+        let response = layer.layer(echo(0)).oneshot(request()).await.unwrap();
+        assert_eq!(response.id, Id::Number(7));
+        assert!(matches!(response.payload, ResponsePayload::Success(id) if *id == 100));
+
+        let err = layer.layer(echo(1)).oneshot(request()).await.unwrap_err();
+        assert!(matches!(err, JsonRpcError::IdMismatch { .. }));
+    }
+
+    #[tokio::test]
+    async fn calls_are_sent_once_polled() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let service = service_fn(move |req: DummyRequest| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                Ok::<_, std::convert::Infallible>(DummyResponse(Response::new(
+                    ResponsePayload::success(serde_json::Value::Null),
+                    req.0.id,
+                )))
+            }
+        });
+
+        let call = service.rpc_call(Request::owned("ping".to_string(), None, Id::Number(7)));
+        tokio::task::yield_now().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        call.await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
 }
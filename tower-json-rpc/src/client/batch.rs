@@ -0,0 +1,364 @@
+//! Batches of client calls.
+//!
+//! [`Batch::call`] queues a typed request, and the methods of the generated `<Trait>Batch`
+//! traits queue a call of their method. [`Batch::send`] then sends the queued calls in one
+//! JSON-RPC array and completes each call with its own response, whatever order the server
+//! answers in:
+//!
+//! ```ignore
+//! let batch = client.batch();
+//! let hello = batch.hello("Ada".into());
+//! let count = batch.call(StatsRequest::Count {});
+//! batch.send().await?;
+//! let (hello, count) = (hello.await?, count.await?);
+//! ```
+//!
+//! The array is sent by a `Service<Vec<Request<'static>>>`, such as the
+//! [`HttpBatchTransport`](super::HttpBatchTransport) of [`HttpTransport::batch`], or an
+//! [`ArrayClient`] posting it through a client service whose transport request can carry it.
+//!
+//! [`HttpTransport::batch`]: super::HttpTransport::batch
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::channel::oneshot;
+use futures_util::future::{join_all, poll_fn};
+use jsonrpsee_types::{Id, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::Service;
+
+use crate::{
+    client::{BatchResponseFuture, ClientRequest, ClientResponse},
+    error::JsonRpcError,
+    server::{ServerRequest, ServerResponse},
+    util::take_ready,
+};
+
+type RequestFuture =
+    Pin<Box<dyn Future<Output = Result<Request<'static>, JsonRpcError>> + Send + 'static>>;
+type Reply = oneshot::Sender<Result<(String, Response<'static, Value>), JsonRpcError>>;
+
+/// The result of a call queued on a [`Batch`], once the batch was sent.
+pub type BatchCall<T> = Pin<Box<dyn Future<Output = Result<T, JsonRpcError>> + Send + 'static>>;
+
+#[derive(Default)]
+struct Queue {
+    sent: bool,
+    calls: Vec<(RequestFuture, Reply)>,
+}
+
+/// Collects calls to send them in one JSON-RPC array with `S`, a
+/// `Service<Vec<Request<'static>>>`. Clones share the same queue.
+pub struct Batch<S> {
+    service: S,
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl<S> Batch<S> {
+    /// Creates an empty batch sending its array with `service`.
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            queue: Arc::default(),
+        }
+    }
+
+    /// Queues `request`, and returns its typed result once the batch was sent. Calls queued
+    /// after [`send`](Self::send) fail.
+    pub fn call<R: ServerRequest>(&self, request: R) -> BatchCall<R::Response> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.sent {
+            return Box::pin(async {
+                Err(JsonRpcError::RequestProcessing(
+                    "the batch was already sent".to_string(),
+                ))
+            });
+        }
+
+        let (reply, response) = oneshot::channel();
+        queue.calls.push((request.into_json_rpc_request(), reply));
+        Box::pin(async move {
+            let (method, response) = response.await.unwrap_or_else(|oneshot::Canceled| {
+                Err(JsonRpcError::RequestProcessing(
+                    "the batch was dropped before being sent".to_string(),
+                ))
+            })?;
+            R::Response::from_method_response(&method, response).await
+        })
+    }
+}
+
+impl<S: Clone> Clone for Batch<S> {
+    fn clone(&self) -> Self {
+        Self {
+            service: self.service.clone(),
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Batch<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queue = self.queue.lock().unwrap();
+        f.debug_struct("Batch")
+            .field("service", &self.service)
+            .field("sent", &queue.sent)
+            .field("calls", &queue.calls.len())
+            .finish()
+    }
+}
+
+impl<S> Batch<S>
+where
+    S: Service<Vec<Request<'static>>, Response = Vec<Response<'static, Value>>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: 'static,
+    S::Error: Into<JsonRpcError>,
+{
+    /// Sends the calls queued so far in one JSON-RPC array, and completes each of them with
+    /// its response. Calls queued afterwards fail.
+    pub async fn send(&self) -> Result<(), JsonRpcError> {
+        let calls = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.sent = true;
+            mem::take(&mut queue.calls)
+        };
+
+        // Number the calls, so their ids are unique within the batch whatever ids they had.
+        let mut replies = HashMap::with_capacity(calls.len());
+        let mut requests = Vec::with_capacity(calls.len());
+        for (index, (request, reply)) in calls.into_iter().enumerate() {
+            let mut request = match request.await {
+                Ok(request) => request,
+                Err(err) => {
+                    let _ = reply.send(Err(err));
+                    continue;
+                }
+            };
+            let id = mem::replace(&mut request.id, Id::Number(index as u64));
+            replies.insert(index as u64, (id, request.method.to_string(), reply));
+            requests.push(request);
+        }
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        let mut service = self.service.clone();
+        let sent = match poll_fn(|cx| service.poll_ready(cx)).await {
+            Ok(()) => service.call(requests).await,
+            Err(err) => Err(err),
+        };
+        let responses = match sent.map_err(Into::into) {
+            Ok(responses) => responses,
+            Err(err) => {
+                for (_, _, reply) in replies.into_values() {
+                    let _ = reply.send(Err(JsonRpcError::RequestProcessing(format!(
+                        "batch failed: {err}"
+                    ))));
+                }
+                return Err(err);
+            }
+        };
+
+        // A server refusing the whole batch answers a single error without id.
+        if let [response] = responses.as_slice()
+            && response.id == Id::Null
+            && let ResponsePayload::Error(err) = &response.payload
+        {
+            for (_, _, reply) in replies.into_values() {
+                let _ = reply.send(Err(JsonRpcError::Rpc(err.clone().into_owned())));
+            }
+            return Ok(());
+        }
+
+        for mut response in responses {
+            let Id::Number(index) = response.id else {
+                continue;
+            };
+            if let Some((id, method, reply)) = replies.remove(&index) {
+                response.id = id;
+                let _ = reply.send(Ok((method, response)));
+            }
+        }
+        for (_, _, reply) in replies.into_values() {
+            let _ = reply.send(Err(JsonRpcError::RequestProcessing(
+                "no response in the batch".to_string(),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sends typed requests in one batch, and returns their results in order.
+    pub async fn send_all<R>(
+        &self,
+        requests: impl IntoIterator<Item = R>,
+    ) -> Result<Vec<Result<R::Response, JsonRpcError>>, JsonRpcError>
+    where
+        R: ServerRequest,
+    {
+        let calls: Vec<_> = requests
+            .into_iter()
+            .map(|request| self.call(request))
+            .collect();
+        self.send().await?;
+        Ok(join_all(calls).await)
+    }
+}
+
+/// Sends the array of a [`Batch`] with a client service, in one transport request built by
+/// [`ClientRequest::from_json_rpc_batch`]. Transport requests that can't carry an array fail
+/// the batch rather than sending its calls one by one.
+pub struct ArrayClient<S, Req> {
+    service: S,
+    _req: PhantomData<fn() -> Req>,
+}
+
+impl<S, Req> ArrayClient<S, Req> {
+    /// Sends the arrays with `service`.
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            _req: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, Req> Clone for ArrayClient<S, Req> {
+    fn clone(&self) -> Self {
+        Self::new(self.service.clone())
+    }
+}
+
+impl<S: fmt::Debug, Req> fmt::Debug for ArrayClient<S, Req> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArrayClient")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl<S, Req> Service<Vec<Request<'static>>> for ArrayClient<S, Req>
+where
+    Req: ClientRequest,
+    S: Service<Req, Response = <Req as ClientRequest>::Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<JsonRpcError>,
+{
+    type Response = Vec<Response<'static, Value>>;
+    type Error = JsonRpcError;
+    type Future = BatchResponseFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, requests: Vec<Request<'static>>) -> Self::Future {
+        let mut service = take_ready(&mut self.service);
+        Box::pin(async move {
+            let request = Req::from_json_rpc_batch(requests).await?;
+            let response = service.call(request).await.map_err(Into::into)?;
+            response.to_json_rpc_batch_response().await
+        })
+    }
+}
+
+/// Starts a [`Batch`] of calls on a client service, sent through an [`ArrayClient`].
+///
+/// This trait is automatically implemented for the services
+/// [`RpcCall`](super::RpcCall) is implemented for.
+/// [`HttpTransport::batch`](super::HttpTransport::batch) takes precedence on the HTTP
+/// transport.
+pub trait RpcBatch<TransportReq>: Sized {
+    fn batch(&self) -> Batch<ArrayClient<Self, TransportReq>>;
+}
+
+impl<T, TransportReq> RpcBatch<TransportReq> for T
+where
+    TransportReq: ClientRequest,
+    T: Service<TransportReq, Response = <TransportReq as ClientRequest>::Response> + Clone,
+{
+    fn batch(&self) -> Batch<ArrayClient<Self, TransportReq>> {
+        Batch::new(ArrayClient::new(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+
+    use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Id, Request, Response, ResponsePayload};
+    use serde_json::Value;
+    use tower::service_fn;
+
+    use crate::client::Batch;
+
+    #[tokio::test]
+    async fn batches_number_their_calls() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let ids = sent.clone();
+        // Answers the methods in reverse order.
+        let service = service_fn(move |requests: Vec<Request<'static>>| {
+            ids.lock()
+                .unwrap()
+                .extend(requests.iter().map(|request| request.id.clone()));
+            let responses: Vec<_> = requests
+                .into_iter()
+                .rev()
+                .map(|request| {
+                    let method = Value::from(request.method.as_ref());
+                    Response::new(ResponsePayload::success(method), request.id)
+                })
+                .collect();
+            async move { Ok::<_, Infallible>(responses) }
+        });
+
+        // Both calls have the same id, the batch numbers them.
+        let requests = ["eth_chainId", "eth_blockNumber"]
+            .map(|method| Request::owned(method.to_string(), None, Id::Number(1)));
+        let responses = Batch::new(service).send_all(requests).await.unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), [Id::Number(0), Id::Number(1)]);
+        let methods: Vec<_> = responses
+            .into_iter()
+            .map(|response| {
+                let response = response.unwrap();
+                assert_eq!(response.id, Id::Number(1));
+                match response.payload {
+                    ResponsePayload::Success(method) => method.into_owned(),
+                    ResponsePayload::Error(err) => panic!("unexpected error {err}"),
+                }
+            })
+            .collect();
+        assert_eq!(methods, ["eth_chainId", "eth_blockNumber"]);
+
+        // A server refusing the whole batch fails every call with its error.
+        let refusing = service_fn(|_: Vec<Request<'static>>| async {
+            let error = ErrorObjectOwned::from(ErrorCode::InvalidRequest);
+            Ok::<_, Infallible>(vec![Response::<Value>::new(
+                ResponsePayload::error(error),
+                Id::Null,
+            )])
+        });
+        let requests = ["eth_chainId", "eth_blockNumber"]
+            .map(|method| Request::owned(method.to_string(), None, Id::Number(1)));
+        let responses = Batch::new(refusing).send_all(requests).await.unwrap();
+        for response in responses {
+            let err = response.unwrap_err();
+            assert_eq!(err.code(), Some(ErrorCode::InvalidRequest.code()));
+        }
+    }
+}
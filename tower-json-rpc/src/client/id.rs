@@ -0,0 +1,199 @@
+//! Ids of the requests sent by clients.
+//!
+//! Typed requests, and the requests of the generated `<Trait>Client` methods and
+//! [`RpcCall`](super::RpcCall), are created with the id 0. The client service gives them their
+//! ids: a [`JsonRpcClient`](super::JsonRpcClient) or an [`HttpTransport`](super::HttpTransport)
+//! sends every request with an id of its own generator, a counter unless replaced with
+//! [`JsonRpcClientLayer::ids`](super::JsonRpcClientLayer::ids) or
+//! [`HttpTransportBuilder::ids`](super::HttpTransportBuilder::ids), and answers it with the id
+//! it was created with.
+
+use std::{
+    fmt::{self, Write},
+    mem,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use jsonrpsee_types::{Id, Request, Response, ResponsePayload};
+use serde_json::Value;
+
+use crate::error::JsonRpcError;
+
+/// Generates the ids of requests.
+pub trait IdGenerator: Send + Sync + 'static {
+    /// The id of the next request, which must differ from the ids of the requests in flight.
+    fn next_id(&self) -> Id<'static>;
+}
+
+/// Generates numeric ids counting up.
+#[derive(Debug, Default)]
+pub struct Counter {
+    next: AtomicU64,
+}
+
+impl Counter {
+    /// Creates a counter starting at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a counter starting at `first`.
+    pub fn starting_at(first: u64) -> Self {
+        Self {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl IdGenerator for Counter {
+    fn next_id(&self) -> Id<'static> {
+        Id::Number(self.next.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Generates random version 4 UUIDs as string ids, such as for clients sharing a connection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uuids;
+
+impl IdGenerator for Uuids {
+    fn next_id(&self) -> Id<'static> {
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes).expect("the system provides random bytes");
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        let mut uuid = String::with_capacity(36);
+        for (i, byte) in bytes.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                uuid.push('-');
+            }
+            let _ = write!(uuid, "{byte:02x}");
+        }
+        Id::Str(uuid.into())
+    }
+}
+
+/// Generates ids with a function provided by the caller.
+impl<F> IdGenerator for F
+where
+    F: Fn() -> Id<'static> + Send + Sync + 'static,
+{
+    fn next_id(&self) -> Id<'static> {
+        self()
+    }
+}
+
+/// The generator of a client, shared by its clones. Defaults to a [`Counter`].
+#[derive(Clone)]
+pub(crate) struct ClientIds(pub(crate) Arc<dyn IdGenerator>);
+
+impl Default for ClientIds {
+    fn default() -> Self {
+        Self(Arc::new(Counter::new()))
+    }
+}
+
+impl fmt::Debug for ClientIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClientIds")
+    }
+}
+
+/// The original id of a request sent with an id of the generator of its client.
+#[derive(Debug)]
+pub(crate) struct SentId {
+    original: Id<'static>,
+    sent: Id<'static>,
+}
+
+impl SentId {
+    /// Gives `request` the next id of `ids`.
+    pub(crate) fn replace(ids: &ClientIds, request: &mut Request<'static>) -> Self {
+        let sent = ids.0.next_id();
+        let original = mem::replace(&mut request.id, sent.clone());
+        Self { original, sent }
+    }
+
+    /// Checks that `response` answers the sent id, and gives it back the original one.
+    pub(crate) fn restore(
+        self,
+        response: Response<'static, Value>,
+    ) -> Result<Response<'static, Value>, JsonRpcError> {
+        let mut response = verify_id(&self.sent, response)?;
+        response.id = self.original;
+        Ok(response)
+    }
+}
+
+/// Rejects a response whose id isn't the id of its request.
+///
+/// Servers answer requests they couldn't parse or read the id of with an error whose id is
+/// `null`, which is returned as [`JsonRpcError::Rpc`].
+pub fn verify_id(
+    expected: &Id<'_>,
+    response: Response<'static, Value>,
+) -> Result<Response<'static, Value>, JsonRpcError> {
+    if response.id == *expected {
+        return Ok(response);
+    }
+    match response.payload {
+        ResponsePayload::Error(err) if response.id == Id::Null => {
+            Err(JsonRpcError::Rpc(err.into_owned()))
+        }
+        _ => Err(JsonRpcError::IdMismatch {
+            expected: expected.clone().into_owned(),
+            actual: response.id,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Id, Response, ResponsePayload};
+    use serde_json::Value;
+
+    use crate::{
+        client::{Counter, IdGenerator, Uuids, verify_id},
+        error::JsonRpcError,
+    };
+
+    #[test]
+    fn generators_produce_distinct_ids() {
+        let counter = Counter::starting_at(7);
+        assert_eq!(counter.next_id(), Id::Number(7));
+        assert_eq!(counter.next_id(), Id::Number(8));
+
+        let (Id::Str(a), Id::Str(b)) = (Uuids.next_id(), Uuids.next_id()) else {
+            panic!("UUIDs are string ids");
+        };
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 36);
+        assert_eq!(a.as_bytes()[14], b'4');
+
+        let fixed = || Id::Str("caller".into());
+        assert_eq!(fixed.next_id(), Id::Str("caller".into()));
+    }
+
+    #[test]
+    fn null_ids_are_accepted_on_errors() {
+        let parse_error = Response::<Value>::new(
+            ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::ParseError)),
+            Id::Null,
+        );
+        let err = verify_id(&Id::Number(3), parse_error).unwrap_err();
+        assert_eq!(err.code(), Some(ErrorCode::ParseError.code()));
+
+        let success = Response::new(ResponsePayload::success(Value::Null), Id::Null);
+        let err = verify_id(&Id::Number(3), success).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonRpcError::IdMismatch {
+                actual: Id::Null,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,439 @@
+//! HTTP transport of JSON-RPC clients.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use http::{
+    HeaderMap, HeaderName, HeaderValue, Uri,
+    header::{AUTHORIZATION, CONTENT_TYPE, PROXY_AUTHORIZATION, USER_AGENT},
+};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder, builderstates::WantsProtocols2};
+use hyper_util::{
+    client::legacy::{
+        Client,
+        connect::{HttpConnector, proxy::Tunnel},
+    },
+    rt::TokioExecutor,
+};
+use jsonrpsee_types::{Request, Response};
+use serde_json::Value;
+use tower::{Service, util::BoxCloneSyncService};
+
+use crate::{
+    client::{
+        Batch, IdGenerator, batch_responses,
+        id::{ClientIds, SentId},
+    },
+    error::JsonRpcError,
+    util::take_ready,
+};
+
+type HttpClient =
+    BoxCloneSyncService<hyper::Request<Full<Bytes>>, hyper::Response<Incoming>, JsonRpcError>;
+
+/// Builds an [`HttpTransport`]. See [`HttpTransport::builder`].
+#[derive(Debug, Clone)]
+pub struct HttpTransportBuilder {
+    url: String,
+    headers: Vec<(HeaderName, String)>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    proxy_auth: Option<String>,
+    ids: ClientIds,
+}
+
+impl HttpTransportBuilder {
+    /// Sends the header with every request.
+    pub fn header(mut self, name: HeaderName, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request.
+    pub fn bearer_auth(self, token: impl AsRef<str>) -> Self {
+        let value = format!("Bearer {}", token.as_ref());
+        self.header(AUTHORIZATION, value)
+    }
+
+    /// Sends the `User-Agent` header with every request.
+    pub fn user_agent(self, user_agent: impl Into<String>) -> Self {
+        self.header(USER_AGENT, user_agent)
+    }
+
+    /// Fails the connections that aren't established within `timeout`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails the calls that aren't answered within `timeout`, including reading the response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connects through an HTTP proxy, tunneling the requests with `CONNECT`.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Sends the `Proxy-Authorization` header with the `CONNECT` requests of the proxy.
+    pub fn proxy_auth(mut self, value: impl Into<String>) -> Self {
+        self.proxy_auth = Some(value.into());
+        self
+    }
+
+    /// Sends the calls with ids from `ids` rather than a counter of the transport.
+    pub fn ids(mut self, ids: impl IdGenerator) -> Self {
+        self.ids = ClientIds(Arc::new(ids));
+        self
+    }
+
+    /// Builds the transport, loading the TLS root certificates of the platform.
+    pub fn build(self) -> Result<HttpTransport, JsonRpcError> {
+        let url = Uri::try_from(self.url).map_err(http::Error::from)?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers {
+            let mut value = HeaderValue::try_from(value).map_err(http::Error::from)?;
+            value.set_sensitive(name == AUTHORIZATION);
+            headers.append(name, value);
+        }
+
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(self.connect_timeout);
+
+        let client = match self.proxy {
+            Some(proxy) => {
+                let proxy = Uri::try_from(proxy).map_err(http::Error::from)?;
+                let mut tunnel = Tunnel::new(proxy, http);
+                if let Some(auth) = self.proxy_auth {
+                    let auth = HeaderValue::try_from(auth).map_err(http::Error::from)?;
+                    tunnel =
+                        tunnel.with_headers(HeaderMap::from_iter([(PROXY_AUTHORIZATION, auth)]));
+                }
+                http_client(https_connector()?.wrap_connector(tunnel))
+            }
+            None => http_client(https_connector()?.wrap_connector(http)),
+        };
+
+        Ok(HttpTransport {
+            client,
+            ids: self.ids,
+            config: Arc::new(Config {
+                url,
+                headers,
+                timeout: self.timeout,
+            }),
+        })
+    }
+}
+
+fn https_connector() -> Result<HttpsConnectorBuilder<WantsProtocols2>, JsonRpcError> {
+    Ok(HttpsConnectorBuilder::new()
+        .with_provider_and_native_roots(rustls::crypto::ring::default_provider())?
+        .https_or_http()
+        .enable_http1())
+}
+
+fn http_client<C>(connector: HttpsConnector<C>) -> HttpClient
+where
+    HttpsConnector<C>: hyper_util::client::legacy::connect::Connect + Clone + Send + Sync + 'static,
+{
+    let client = Client::builder(TokioExecutor::new()).build(connector);
+    BoxCloneSyncService::new(tower::ServiceExt::map_err(client, JsonRpcError::from))
+}
+
+#[derive(Debug)]
+struct Config {
+    url: Uri,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+}
+
+/// A JSON-RPC client transport posting calls to an HTTP(S) endpoint.
+///
+/// It implements `Service<Request<'static>>`, so the extension methods of the generated
+/// `<Trait>Client` traits can be called on it directly:
+///
+/// ```ignore
+/// let transport = HttpTransport::builder("https://rpc.example.com")
+///     .bearer_auth(token)
+///     .timeout(Duration::from_secs(10))
+///     .build()?;
+/// let block = transport.block_number().await?;
+/// ```
+///
+/// Its [`batch`](Self::batch) posts the calls of a batch in one JSON-RPC array. Clones share
+/// the same connection pool and id generator.
+#[derive(Clone)]
+pub struct HttpTransport {
+    client: HttpClient,
+    ids: ClientIds,
+    config: Arc<Config>,
+}
+
+impl HttpTransport {
+    /// Creates a transport posting calls to `url`, with the default settings.
+    pub fn new(url: impl Into<String>) -> Result<Self, JsonRpcError> {
+        Self::builder(url).build()
+    }
+
+    /// Starts building a transport posting calls to `url`.
+    pub fn builder(url: impl Into<String>) -> HttpTransportBuilder {
+        HttpTransportBuilder {
+            url: url.into(),
+            headers: Vec::new(),
+            connect_timeout: None,
+            timeout: None,
+            proxy: None,
+            proxy_auth: None,
+            ids: ClientIds::default(),
+        }
+    }
+
+    /// The endpoint the calls are posted to.
+    pub fn url(&self) -> &Uri {
+        &self.config.url
+    }
+
+    /// Starts a [`Batch`] of calls, posted in one JSON-RPC array.
+    pub fn batch(&self) -> Batch<HttpBatchTransport> {
+        Batch::new(HttpBatchTransport(self.clone()))
+    }
+
+    /// Posts `body` and parses the answer with `parse`.
+    fn post<T: Send + 'static>(
+        &mut self,
+        body: serde_json::Result<Vec<u8>>,
+        parse: fn(&[u8]) -> serde_json::Result<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<T, JsonRpcError>> + Send + 'static>> {
+        let mut client = take_ready(&mut self.client);
+        let config = self.config.clone();
+
+        let call = async move {
+            let mut http_request = hyper::Request::post(config.url.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .body(Full::new(Bytes::from(body?)))?;
+            for (name, value) in &config.headers {
+                http_request.headers_mut().append(name, value.clone());
+            }
+
+            let response = client.call(http_request).await?;
+            let status = response.status();
+            let body = response.into_body().collect().await?.to_bytes();
+            // Servers may answer JSON-RPC errors with an error status, so only fail on the
+            // status when the body isn't a response.
+            match parse(&body) {
+                Ok(response) => Ok(response),
+                Err(_) if !status.is_success() => Err(JsonRpcError::HttpStatus(status)),
+                Err(err) => Err(err.into()),
+            }
+        };
+
+        match self.config.timeout {
+            Some(timeout) => Box::pin(async move {
+                tokio::time::timeout(timeout, call)
+                    .await
+                    .unwrap_or(Err(JsonRpcError::Timeout(timeout)))
+            }),
+            None => Box::pin(call),
+        }
+    }
+}
+
+impl std::fmt::Debug for HttpTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpTransport")
+            .field("config", &self.config)
+            .field("ids", &self.ids)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Service<Request<'static>> for HttpTransport {
+    type Response = Response<'static, Value>;
+    type Error = JsonRpcError;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.client.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<'static>) -> Self::Future {
+        let sent_id = SentId::replace(&self.ids, &mut request);
+        let response = self.post(serde_json::to_vec(&request), |body| {
+            Ok(serde_json::from_slice::<Response<'_, Value>>(body)?.into_owned())
+        });
+        Box::pin(async move { sent_id.restore(response.await?) })
+    }
+}
+
+/// Posts the requests of a batch in one JSON-RPC array, with the settings of the
+/// [`HttpTransport`] it was created from by [`HttpTransport::batch`].
+#[derive(Debug, Clone)]
+pub struct HttpBatchTransport(HttpTransport);
+
+impl Service<Vec<Request<'static>>> for HttpBatchTransport {
+    type Response = Vec<Response<'static, Value>>;
+    type Error = JsonRpcError;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.client.poll_ready(cx)
+    }
+
+    fn call(&mut self, requests: Vec<Request<'static>>) -> Self::Future {
+        self.0.post(serde_json::to_vec(&requests), batch_responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use axum::{Router, http::HeaderMap, http::StatusCode, routing::post};
+    use jsonrpsee_types::{Id, Request, ResponsePayload};
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    use crate::{
+        client::{Counter, HttpTransport},
+        error::JsonRpcError,
+    };
+
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        format!("http://{addr}/rpc")
+    }
+
+    #[tokio::test]
+    async fn posts_calls_with_default_headers() {
+        let url = serve(
+            Router::new()
+                .route(
+                    "/rpc",
+                    post(|headers: HeaderMap, body: String| async move {
+                        let request: Value = serde_json::from_str(&body).unwrap();
+                        let header = |name: &str| headers[name].to_str().unwrap().to_string();
+                        axum::Json(json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "result": [header("authorization"), header("user-agent")],
+                        }))
+                    }),
+                )
+                .route(
+                    "/slow",
+                    post(|| async {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }),
+                )
+                .route("/down", post(|| async { StatusCode::BAD_GATEWAY }))
+                .route(
+                    "/id",
+                    post(|body: String| async move {
+                        let request: Value = serde_json::from_str(&body).unwrap();
+                        axum::Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["id"] }))
+                    }),
+                ),
+        )
+        .await;
+        let request = || Request::owned("eth_chainId".to_string(), None, Id::Number(4));
+
+        let transport = HttpTransport::builder(&url)
+            .bearer_auth("secret")
+            .user_agent("tower-json-rpc-test")
+            .build()
+            .unwrap();
+        let response = transport.oneshot(request()).await.unwrap();
+        assert_eq!(response.id, Id::Number(4));
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Success(value) if *value == json!(["Bearer secret", "tower-json-rpc-test"])
+        ));
+
+        let slow = HttpTransport::builder(url.replace("/rpc", "/slow"))
+            .timeout(Duration::from_millis(20))
+            .build()
+            .unwrap();
+        let err = slow.oneshot(request()).await.unwrap_err();
+        assert!(matches!(err, JsonRpcError::Timeout(_)));
+
+        let down = HttpTransport::new(url.replace("/rpc", "/down")).unwrap();
+        let err = down.oneshot(request()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            JsonRpcError::HttpStatus(StatusCode::BAD_GATEWAY)
+        ));
+
+        // The transport sends its own ids, and answers with the ids of the requests.
+        let ids = HttpTransport::builder(url.replace("/rpc", "/id"))
+            .ids(Counter::starting_at(100))
+            .build()
+            .unwrap();
+        let response = ids.oneshot(request()).await.unwrap();
+        assert_eq!(response.id, Id::Number(4));
+        assert!(matches!(response.payload, ResponsePayload::Success(id) if *id == 100));
+    }
+
+    #[tokio::test]
+    async fn batches_are_posted_in_one_array() {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let counter = posts.clone();
+        let url = serve(Router::new().route(
+            "/rpc",
+            post(move |body: String| async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let requests: Vec<Value> = serde_json::from_str(&body).unwrap();
+                let responses: Vec<Value> = requests
+                    .iter()
+                    .map(|request| {
+                        json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["method"] })
+                    })
+                    .collect();
+                axum::Json(responses)
+            }),
+        ))
+        .await;
+
+        let transport = HttpTransport::new(url).unwrap();
+        let batch = transport.batch();
+        let calls = ["eth_chainId", "eth_blockNumber", "eth_gasPrice"]
+            .map(|method| batch.call(Request::owned(method.to_string(), None, Id::Number(1))));
+        batch.send().await.unwrap();
+
+        assert_eq!(posts.load(Ordering::SeqCst), 1);
+        for (call, method) in
+            calls
+                .into_iter()
+                .zip(["eth_chainId", "eth_blockNumber", "eth_gasPrice"])
+        {
+            let response = call.await.unwrap();
+            assert_eq!(response.id, Id::Number(1));
+            assert!(
+                matches!(response.payload, ResponsePayload::Success(value) if *value == method)
+            );
+        }
+    }
+}
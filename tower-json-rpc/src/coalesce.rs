@@ -0,0 +1,280 @@
+//! Coalescing of identical in-flight calls.
+//!
+//! When many clients make the same call at the same time, such as fetching a block right after
+//! it is produced, [`CoalesceLayer`] forwards only the first call to the inner service. The
+//! identical calls made while it is in flight wait for its result, which is answered to each
+//! of them with their own request id. Calls to the aliases of a method are identical to calls
+//! to the method.
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::ready,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::channel::oneshot;
+use futures_util::{FutureExt, future::Either};
+use jsonrpsee_types::{Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::{Layer, Service, ServiceExt};
+
+use crate::{cache::cache_key, method::MethodInfo, server::BoxFuture, util::take_ready};
+
+type Key = (String, String);
+type Waiter = oneshot::Sender<ResponsePayload<'static, Value>>;
+type InFlight = Arc<Mutex<HashMap<Key, Vec<Waiter>>>>;
+
+#[derive(Debug, Clone, Default)]
+struct Methods {
+    all: bool,
+    /// Coalesced methods by name or alias, along with the name their calls are keyed under.
+    included: HashMap<String, String>,
+    excluded: HashSet<String>,
+}
+
+impl Methods {
+    /// The name the calls to `method` are keyed under, if they are coalesced.
+    fn coalesced<'a>(&'a self, method: &'a str) -> Option<&'a str> {
+        if self.excluded.contains(method) {
+            return None;
+        }
+        match self.included.get(method) {
+            Some(name) => Some(name),
+            None => self.all.then_some(method),
+        }
+    }
+}
+
+/// A layer that shares the result of a call with the identical calls made while it is in
+/// flight.
+///
+/// ```ignore
+/// let layer = CoalesceLayer::new()
+///     .all()
+///     .exclude("eth_sendRawTransaction");
+/// ```
+///
+/// Calls are identical when they have the same method and params. Waiting calls whose first
+/// call fails with an error of the inner service, or is cancelled, are forwarded to the inner
+/// service on their own.
+#[derive(Debug, Clone, Default)]
+pub struct CoalesceLayer {
+    methods: Arc<Methods>,
+    in_flight: InFlight,
+}
+
+impl CoalesceLayer {
+    /// Creates a layer that doesn't coalesce any method.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coalesces every method that isn't excluded.
+    pub fn all(mut self) -> Self {
+        Arc::make_mut(&mut self.methods).all = true;
+        self
+    }
+
+    /// Coalesces the calls to the method `name`.
+    pub fn method(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        Arc::make_mut(&mut self.methods)
+            .included
+            .insert(name.clone(), name);
+        self
+    }
+
+    /// Coalesces the methods declared with `#[method(cacheable)]`, including aliases.
+    pub fn methods(mut self, methods: &[MethodInfo]) -> Self {
+        let included = &mut Arc::make_mut(&mut self.methods).included;
+        for info in methods.iter().filter(|info| info.cacheable) {
+            included.extend(
+                info.names()
+                    .map(|name| (name.to_string(), info.name.to_string())),
+            );
+        }
+        self
+    }
+
+    /// Never coalesces the calls to the method `name`, such as methods with side effects.
+    pub fn exclude(mut self, name: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.methods)
+            .excluded
+            .insert(name.into());
+        self
+    }
+}
+
+impl<S> Layer<S> for CoalesceLayer {
+    type Service = Coalesce<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Coalesce {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`CoalesceLayer`].
+#[derive(Debug, Clone)]
+pub struct Coalesce<S> {
+    inner: S,
+    layer: CoalesceLayer,
+}
+
+/// Removes the in-flight call when it completes or is dropped. Waiters of a dropped call see
+/// their sender dropped, and call the inner service themselves.
+struct Flight {
+    key: Option<Key>,
+    in_flight: InFlight,
+}
+
+impl Flight {
+    fn finish(mut self) -> Vec<Waiter> {
+        let key = self.key.take().expect("a flight finishes once");
+        self.in_flight
+            .lock()
+            .unwrap()
+            .remove(&key)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.in_flight.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+impl<S> Service<Request<'static>> for Coalesce<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let key = match self.layer.methods.coalesced(&request.method) {
+            Some(name) => cache_key(name, request.params.as_deref()),
+            None => return Box::pin(self.inner.call(request)),
+        };
+        let mut in_flight = self.layer.in_flight.lock().unwrap();
+
+        // Waiters leave the ready service to the next call, and only wait for one if their
+        // first call fails.
+        if let Some(waiters) = in_flight.get_mut(&key) {
+            let (sender, receiver) = oneshot::channel();
+            waiters.push(sender);
+            drop(in_flight);
+            let inner = self.inner.clone();
+
+            return Box::pin(receiver.then(move |received| match received {
+                Ok(payload) => Either::Left(ready(Ok(Response::new(payload, request.id)))),
+                Err(oneshot::Canceled) => Either::Right(inner.oneshot(request)),
+            }));
+        }
+
+        in_flight.insert(key.clone(), Vec::new());
+        drop(in_flight);
+        let flight = Flight {
+            key: Some(key),
+            in_flight: self.layer.in_flight.clone(),
+        };
+
+        let mut inner = take_ready(&mut self.inner);
+        Box::pin(inner.call(request).map(move |result| {
+            let waiters = flight.finish();
+            // Responses are shared, including JSON-RPC errors. Waiters of a call failing with
+            // an error of the service are dropped, and make the call themselves.
+            if let Ok(response) = &result {
+                for waiter in waiters {
+                    let _ = waiter.send(response.payload.clone());
+                }
+            }
+            result
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use jsonrpsee_types::{Id, Request, Response, ResponsePayload};
+    use serde_json::Value;
+    use tower::{Layer, service_fn};
+
+    use crate::{
+        coalesce::CoalesceLayer,
+        method::MethodInfo,
+        test_util::{call, request},
+    };
+
+    #[tokio::test]
+    async fn identical_calls_share_one_result() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let svc = CoalesceLayer::new()
+            .all()
+            .exclude("eth_sendRawTransaction")
+            .layer(service_fn(move |req: Request<'static>| {
+                let count = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok::<_, std::convert::Infallible>(Response::new(
+                        ResponsePayload::success(Value::from(count)),
+                        req.id,
+                    ))
+                }
+            }));
+
+        let call = |method: &str, id: u64| call(&svc, request(method, id));
+
+        let responses = futures::future::join_all((0..3).map(|id| call("eth_getBlock", id))).await;
+        for (id, response) in responses.into_iter().enumerate() {
+            let response = response.unwrap();
+            assert_eq!(response.id, Id::Number(id as u64));
+            assert!(matches!(response.payload, ResponsePayload::Success(value) if *value == 0));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        futures::future::join_all((0..3).map(|id| call("eth_sendRawTransaction", id))).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // Aliases of a method share its calls.
+        let svc = CoalesceLayer::new()
+            .methods(&[MethodInfo::new("eth_getBlock")
+                .with_aliases(&["eth_block"])
+                .with_cacheable()])
+            .layer(svc);
+        let responses = futures::future::join_all(
+            ["eth_getBlock", "eth_block"]
+                .into_iter()
+                .zip(5..)
+                .map(|(method, id)| crate::test_util::call(&svc, request(method, id))),
+        )
+        .await;
+        for response in responses {
+            let response = response.unwrap();
+            assert!(matches!(response.payload, ResponsePayload::Success(value) if *value == 4));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+}
@@ -0,0 +1,411 @@
+//! Compute-unit accounting of JSON-RPC calls against per-caller quotas.
+//!
+//! Methods are given a [`Cost`] in compute units with `#[method(cost = ...)]`, either fixed or
+//! computed from the params. [`QuotaLayer`] charges the cost of every call to the caller in a
+//! [`QuotaLedger`], and rejects the calls that would exceed the quota of the caller.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::ready,
+    mem,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures_util::{FutureExt, future::Either};
+use jsonrpsee_types::{ErrorObjectOwned, Params, Request, Response, ResponsePayload};
+use serde_json::Value;
+use thiserror::Error;
+use tower::{Layer, Service};
+
+use crate::{method::MethodInfo, rate_limit::caller_identity, server::BoxFuture, util::take_ready};
+
+/// Error code of the quota exceeded error.
+pub const QUOTA_EXCEEDED_CODE: i32 = -32006;
+
+/// The cost of a call to an RPC method, in compute units.
+#[derive(Clone, Copy)]
+pub enum Cost {
+    /// The same cost for every call.
+    Fixed(u64),
+    /// A cost computed from the params of the call, such as the block range of a log query.
+    Dynamic(fn(&Params<'_>) -> u64),
+}
+
+impl Cost {
+    /// The cost of a call with `params`.
+    pub fn of(&self, params: &Params<'_>) -> u64 {
+        match self {
+            Cost::Fixed(units) => *units,
+            Cost::Dynamic(cost) => cost(params),
+        }
+    }
+}
+
+impl fmt::Debug for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cost::Fixed(units) => f.debug_tuple("Fixed").field(units).finish(),
+            Cost::Dynamic(_) => f.write_str("Dynamic(..)"),
+        }
+    }
+}
+
+impl PartialEq for Cost {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Cost::Fixed(a), Cost::Fixed(b)) => a == b,
+            (Cost::Dynamic(a), Cost::Dynamic(b)) => std::ptr::fn_addr_eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Cost {}
+
+/// Returned by a [`QuotaLedger`] when a charge would exceed the quota of the caller.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("quota exceeded: {used} of {quota} compute units used, {requested} requested")]
+pub struct QuotaExceeded {
+    /// Units used by the caller so far.
+    pub used: u64,
+    /// Quota of the caller.
+    pub quota: u64,
+    /// Units of the rejected call.
+    pub requested: u64,
+}
+
+impl From<QuotaExceeded> for ErrorObjectOwned {
+    fn from(err: QuotaExceeded) -> Self {
+        ErrorObjectOwned::owned(
+            QUOTA_EXCEEDED_CODE,
+            "quota exceeded",
+            Some(serde_json::json!({
+                "used": err.used,
+                "quota": err.quota,
+                "requested": err.requested,
+            })),
+        )
+    }
+}
+
+/// The compute units used by a caller, as exported for billing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// Identity of the caller.
+    pub caller: String,
+    /// Units charged to the caller.
+    pub used: u64,
+    /// Quota of the caller, if it has one.
+    pub quota: Option<u64>,
+}
+
+/// Storage of the compute units charged to each caller.
+///
+/// Implement it to keep the usage in a shared database, so that quotas hold across several
+/// servers. [`MemoryLedger`] keeps it in memory.
+pub trait QuotaLedger: Clone + Send + Sync + 'static {
+    /// Charges `units` to `caller`, or fails without charging anything if that would exceed
+    /// the quota of the caller.
+    fn charge(&self, caller: String, units: u64) -> BoxFuture<Result<(), QuotaExceeded>>;
+
+    /// Usage of every caller charged so far.
+    fn usage(&self) -> BoxFuture<Vec<Usage>>;
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    default_quota: Option<u64>,
+    quotas: HashMap<String, u64>,
+    used: HashMap<String, u64>,
+}
+
+impl Ledger {
+    fn quota(&self, caller: &str) -> Option<u64> {
+        self.quotas.get(caller).copied().or(self.default_quota)
+    }
+}
+
+/// A [`QuotaLedger`] kept in memory. Clones share the same usage.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryLedger {
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl MemoryLedger {
+    /// Creates a ledger where callers have no quota.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the quota of the callers without their own quota.
+    pub fn with_default_quota(self, quota: u64) -> Self {
+        self.ledger.lock().unwrap().default_quota = Some(quota);
+        self
+    }
+
+    /// Sets the quota of `caller`.
+    pub fn set_quota(&self, caller: impl Into<String>, quota: u64) {
+        self.ledger
+            .lock()
+            .unwrap()
+            .quotas
+            .insert(caller.into(), quota);
+    }
+
+    /// Units charged to `caller` so far.
+    pub fn used(&self, caller: &str) -> u64 {
+        self.ledger
+            .lock()
+            .unwrap()
+            .used
+            .get(caller)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Clears the usage of every caller, such as at the start of a billing period, and returns
+    /// it.
+    pub fn reset(&self) -> Vec<Usage> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let used = mem::take(&mut ledger.used);
+        used.into_iter()
+            .map(|(caller, used)| Usage {
+                quota: ledger.quota(&caller),
+                caller,
+                used,
+            })
+            .collect()
+    }
+}
+
+impl QuotaLedger for MemoryLedger {
+    fn charge(&self, caller: String, units: u64) -> BoxFuture<Result<(), QuotaExceeded>> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let quota = ledger.quota(&caller);
+        let used = ledger.used.entry(caller).or_default();
+
+        let result = match quota {
+            Some(quota) if used.saturating_add(units) > quota => Err(QuotaExceeded {
+                used: *used,
+                quota,
+                requested: units,
+            }),
+            _ => {
+                *used = used.saturating_add(units);
+                Ok(())
+            }
+        };
+        Box::pin(ready(result))
+    }
+
+    fn usage(&self) -> BoxFuture<Vec<Usage>> {
+        let ledger = self.ledger.lock().unwrap();
+        let usage = ledger
+            .used
+            .iter()
+            .map(|(caller, used)| Usage {
+                caller: caller.clone(),
+                used: *used,
+                quota: ledger.quota(caller),
+            })
+            .collect();
+        Box::pin(ready(usage))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Costs {
+    default: Cost,
+    methods: HashMap<String, Cost>,
+    caller: fn(&Request<'static>) -> Option<String>,
+}
+
+/// A layer that charges the cost of every call to the quota of its caller.
+///
+/// ```ignore
+/// let ledger = MemoryLedger::new().with_default_quota(1_000_000);
+/// let layer = QuotaLayer::new(ledger.clone())
+///     .methods(<EthService as RpcMethods>::INFO)
+///     .method("eth_getLogs", Cost::Fixed(75));
+///
+/// // Later, for the billing export:
+/// let usage = ledger.usage().await;
+/// ```
+///
+/// Callers are identified with [`caller_identity`] by default. The ones without an identity
+/// are charged together, under an empty caller.
+#[derive(Debug, Clone)]
+pub struct QuotaLayer<L> {
+    ledger: L,
+    costs: Arc<Costs>,
+}
+
+impl<L: QuotaLedger> QuotaLayer<L> {
+    /// Creates a layer charging one unit per call to `ledger`.
+    pub fn new(ledger: L) -> Self {
+        Self {
+            ledger,
+            costs: Arc::new(Costs {
+                default: Cost::Fixed(1),
+                methods: HashMap::new(),
+                caller: caller_identity,
+            }),
+        }
+    }
+
+    /// Sets the cost of the methods without their own cost.
+    pub fn default_cost(mut self, cost: Cost) -> Self {
+        Arc::make_mut(&mut self.costs).default = cost;
+        self
+    }
+
+    /// Sets the cost of the method `name`.
+    pub fn method(mut self, name: impl Into<String>, cost: Cost) -> Self {
+        Arc::make_mut(&mut self.costs)
+            .methods
+            .insert(name.into(), cost);
+        self
+    }
+
+    /// Sets the costs declared with `#[method(cost = ...)]`, including aliases.
+    pub fn methods(mut self, methods: &[MethodInfo]) -> Self {
+        let costs = Arc::make_mut(&mut self.costs);
+        for info in methods {
+            if let Some(cost) = info.cost {
+                costs
+                    .methods
+                    .extend(info.names().map(|name| (name.to_string(), cost)));
+            }
+        }
+        self
+    }
+
+    /// Sets how callers are identified, instead of [`caller_identity`].
+    pub fn caller(mut self, caller: fn(&Request<'static>) -> Option<String>) -> Self {
+        Arc::make_mut(&mut self.costs).caller = caller;
+        self
+    }
+}
+
+impl<S, L: Clone> Layer<S> for QuotaLayer<L> {
+    type Service = Quota<S, L>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Quota {
+            inner,
+            ledger: self.ledger.clone(),
+            costs: self.costs.clone(),
+        }
+    }
+}
+
+/// Service produced by [`QuotaLayer`].
+#[derive(Debug, Clone)]
+pub struct Quota<S, L> {
+    inner: S,
+    ledger: L,
+    costs: Arc<Costs>,
+}
+
+impl<S, L> Service<Request<'static>> for Quota<S, L>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    L: QuotaLedger,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let cost = self
+            .costs
+            .methods
+            .get(request.method.as_ref())
+            .unwrap_or(&self.costs.default)
+            .of(&request.params());
+        let caller = (self.costs.caller)(&request).unwrap_or_default();
+
+        let mut inner = take_ready(&mut self.inner);
+
+        Box::pin(
+            self.ledger
+                .charge(caller, cost)
+                .then(move |charged| match charged {
+                    Ok(()) => Either::Left(inner.call(request)),
+                    Err(err) => Either::Right(ready(Ok(Response::new(
+                        ResponsePayload::error(ErrorObjectOwned::from(err)),
+                        request.id,
+                    )))),
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee_types::{Id, Params, Request, ResponsePayload};
+    use serde_json::Value;
+    use tower::Layer;
+
+    use crate::{
+        auth::Principal,
+        cost::{Cost, MemoryLedger, QUOTA_EXCEEDED_CODE, QuotaLayer, QuotaLedger, Usage},
+        test_util::{call, echo},
+    };
+
+    fn block_range(params: &Params<'_>) -> u64 {
+        let (from, to): (u64, u64) = params.parse().unwrap_or_default();
+        to.saturating_sub(from) + 1
+    }
+
+    #[tokio::test]
+    async fn charges_callers_until_their_quota() {
+        let ledger = MemoryLedger::new().with_default_quota(10);
+        let svc = QuotaLayer::new(ledger.clone())
+            .method("eth_getLogs", Cost::Dynamic(block_range))
+            .layer(echo());
+
+        let call = |method: &str, params: Value| {
+            let params = serde_json::value::to_raw_value(&params).unwrap();
+            let mut request = Request::owned(method.to_string(), Some(params), Id::Number(4));
+            request.extensions.insert(Principal::new("ada"));
+            call(&svc, request)
+        };
+
+        let response = call("eth_getLogs", serde_json::json!([1, 8]))
+            .await
+            .unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+        let response = call("eth_chainId", Value::Null).await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+        assert_eq!(ledger.used("ada"), 9);
+
+        let response = call("eth_getLogs", serde_json::json!([1, 2]))
+            .await
+            .unwrap();
+        assert_eq!(response.id, Id::Number(4));
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Error(err) if err.code() == QUOTA_EXCEEDED_CODE
+        ));
+
+        let response = call("eth_chainId", Value::Null).await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+        assert_eq!(
+            ledger.usage().await,
+            vec![Usage {
+                caller: "ada".to_string(),
+                used: 10,
+                quota: Some(10),
+            }]
+        );
+    }
+}
@@ -1,9 +1,13 @@
-use std::convert::Infallible;
+use std::{convert::Infallible, time::Duration};
 
+use jsonrpsee_types::ErrorObjectOwned;
+use serde::de::DeserializeOwned;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum JsonRpcError {
+    #[error("RPC error {}: {}", .0.code(), .0.message())]
+    Rpc(#[from] ErrorObjectOwned),
     #[error("Request processing error: {0}")]
     RequestProcessing(String),
     #[error("Response serialization error: {0}")]
@@ -22,6 +26,44 @@ pub enum JsonRpcError {
     Hyper(#[from] hyper::Error),
     #[error(transparent)]
     Axum(#[from] axum::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("HTTP error status: {0}")]
+    HttpStatus(http::StatusCode),
+    #[error("response id {actual} doesn't match request id {expected}")]
+    IdMismatch {
+        expected: jsonrpsee_types::Id<'static>,
+        actual: jsonrpsee_types::Id<'static>,
+    },
+}
+
+impl JsonRpcError {
+    /// The error answered by the server, if the call failed with one.
+    pub fn rpc_error(&self) -> Option<&ErrorObjectOwned> {
+        match self {
+            JsonRpcError::Rpc(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The code of the error answered by the server.
+    pub fn code(&self) -> Option<i32> {
+        self.rpc_error().map(ErrorObjectOwned::code)
+    }
+
+    /// The message of the error answered by the server.
+    pub fn message(&self) -> Option<&str> {
+        self.rpc_error().map(ErrorObjectOwned::message)
+    }
+
+    /// The data of the error answered by the server, decoded as `T`, such as the revert data
+    /// of a failed `eth_call`. `None` when the call didn't fail with an error carrying data.
+    pub fn data<T: DeserializeOwned>(&self) -> Option<Result<T, serde_json::Error>> {
+        let data = self.rpc_error()?.data()?;
+        Some(serde_json::from_str(data.get()))
+    }
 }
 
 impl From<Infallible> for JsonRpcError {
@@ -0,0 +1,241 @@
+//! Extractors for RPC handler arguments that are filled from the request instead of `params`.
+//!
+//! A handler argument marked with `#[argument(extract)]` is built with [`FromRequestParts`]
+//! before the handler runs. The argument is not part of the wire format, so it doesn't show
+//! up in the generated request enum or in the client methods.
+
+use std::{
+    any::type_name,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http::{Extensions, HeaderMap};
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Id, Request};
+use tower::{Layer, Service};
+
+/// The parts of a JSON-RPC request that extractors can read from.
+#[derive(Debug, Clone)]
+pub struct RequestParts {
+    /// The called method name.
+    pub method: String,
+    /// The id of the request.
+    pub id: Id<'static>,
+    /// The request extensions, including those of the transport request.
+    pub extensions: Extensions,
+}
+
+impl RequestParts {
+    /// Copies the parts of `request` needed by the extractors.
+    pub fn from_request(request: &Request<'_>) -> Self {
+        Self {
+            method: request.method.to_string(),
+            id: request.id.clone().into_owned(),
+            extensions: request.extensions.clone(),
+        }
+    }
+}
+
+/// Types that can be created from [`RequestParts`] to be passed to a handler method.
+pub trait FromRequestParts: Sized {
+    /// Builds the argument, or returns the error to answer the request with.
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned>;
+}
+
+fn missing_extension<T>() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        ErrorCode::InternalError.code(),
+        ErrorCode::InternalError.message(),
+        Some(format!("Missing request extension `{}`", type_name::<T>())),
+    )
+}
+
+/// Extracts a clone of an extension of type `T`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Extension<T>(pub T);
+
+impl<T> FromRequestParts for Extension<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        parts
+            .extensions
+            .get::<T>()
+            .cloned()
+            .map(Extension)
+            .ok_or_else(missing_extension::<T>)
+    }
+}
+
+/// Extracts shared state inserted by [`StateLayer`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct State<T>(pub T);
+
+impl<T> FromRequestParts for State<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        parts
+            .extensions
+            .get::<State<T>>()
+            .cloned()
+            .ok_or_else(missing_extension::<State<T>>)
+    }
+}
+
+/// Extracts the id of the request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub Id<'static>);
+
+impl FromRequestParts for RequestId {
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        Ok(RequestId(parts.id.clone()))
+    }
+}
+
+/// Extracts the headers of the HTTP request that carried the call.
+#[derive(Debug, Clone, Default)]
+pub struct Headers(pub HeaderMap);
+
+impl FromRequestParts for Headers {
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        parts
+            .extensions
+            .get::<HeaderMap>()
+            .cloned()
+            .map(Headers)
+            .ok_or_else(missing_extension::<HeaderMap>)
+    }
+}
+
+/// Extracts the address of the remote peer.
+///
+/// Reads a [`SocketAddr`] extension, or axum's `ConnectInfo<SocketAddr>` when the app is
+/// served with `into_make_service_with_connect_info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteAddr(pub SocketAddr);
+
+impl FromRequestParts for RemoteAddr {
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        parts
+            .extensions
+            .get::<SocketAddr>()
+            .copied()
+            .or_else(|| {
+                parts
+                    .extensions
+                    .get::<axum::extract::ConnectInfo<SocketAddr>>()
+                    .map(|info| info.0)
+            })
+            .map(RemoteAddr)
+            .ok_or_else(missing_extension::<SocketAddr>)
+    }
+}
+
+impl FromRequestParts for Extensions {
+    fn from_request_parts(parts: &RequestParts) -> Result<Self, ErrorObjectOwned> {
+        Ok(parts.extensions.clone())
+    }
+}
+
+/// A layer that makes `state` available to the [`State`] extractor.
+#[derive(Debug, Clone)]
+pub struct StateLayer<T> {
+    state: T,
+}
+
+impl<T> StateLayer<T> {
+    /// Creates a layer that shares `state` with every request.
+    pub fn new(state: T) -> Self {
+        Self { state }
+    }
+}
+
+impl<S, T: Clone> Layer<S> for StateLayer<T> {
+    type Service = StateService<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StateService {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Inserts a [`State`] into the extensions of every request.
+#[derive(Debug, Clone)]
+pub struct StateService<S, T> {
+    inner: S,
+    state: T,
+}
+
+impl<S, T> Service<Request<'static>> for StateService<S, T>
+where
+    S: Service<Request<'static>>,
+    S::Future: Send + 'static,
+    T: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<'static>) -> Self::Future {
+        request.extensions.insert(State(self.state.clone()));
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use http::HeaderMap;
+    use jsonrpsee_types::{ErrorCode, Id, Request};
+
+    use crate::extract::{
+        Extension, FromRequestParts, Headers, RemoteAddr, RequestId, RequestParts, State,
+    };
+
+    #[test]
+    fn extractors_read_request_parts() {
+        let mut request: Request<'static> =
+            Request::owned("say_hello".to_string(), None, Id::Number(3));
+        let addr: SocketAddr = "127.0.0.1:8545".parse().unwrap();
+        request.extensions.insert(addr);
+        request.extensions.insert(HeaderMap::new());
+        request.extensions.insert(State(7u8));
+        request.extensions.insert(String::from("admin"));
+
+        let parts = RequestParts::from_request(&request);
+
+        assert_eq!(
+            RequestId::from_request_parts(&parts).unwrap(),
+            RequestId(Id::Number(3))
+        );
+        assert_eq!(RemoteAddr::from_request_parts(&parts).unwrap().0, addr);
+        assert_eq!(State::<u8>::from_request_parts(&parts).unwrap().0, 7);
+        assert_eq!(
+            Extension::<String>::from_request_parts(&parts).unwrap().0,
+            "admin"
+        );
+        assert!(Headers::from_request_parts(&parts).is_ok());
+    }
+
+    #[test]
+    fn missing_extension_is_internal_error() {
+        let request: Request<'static> = Request::owned("say_hello".to_string(), None, Id::Null);
+        let parts = RequestParts::from_request(&request);
+
+        let err = Extension::<u64>::from_request_parts(&parts).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InternalError.code());
+    }
+}
@@ -0,0 +1,285 @@
+//! Allow and deny lists of the methods exposed by a deployment.
+//!
+//! [`MethodFilterLayer`] answers the calls to filtered methods with the same `MethodNotFound`
+//! error as unknown methods, so filtered methods can't be discovered. Its [`MethodRules`] can
+//! be replaced at runtime through a [`MethodFilterHandle`], without restarting the server.
+
+use std::{
+    future::{Ready, ready},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::future::Either;
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tokio::sync::watch;
+use tower::{Layer, Service};
+
+/// A rule matching method names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodRule {
+    /// Matches a single method.
+    Exact(String),
+    /// Matches the methods starting with the prefix.
+    Prefix(String),
+    /// Matches the methods against a pattern where `*` matches any sequence of characters and
+    /// `?` any single character.
+    Glob(String),
+}
+
+impl MethodRule {
+    /// Matches the method `name`.
+    pub fn exact(name: impl Into<String>) -> Self {
+        Self::Exact(name.into())
+    }
+
+    /// Matches the methods of a namespace with the default `_` separator, so `eth` matches
+    /// `eth_chainId`.
+    pub fn namespace(namespace: impl Into<String>) -> Self {
+        Self::Prefix(namespace.into() + "_")
+    }
+
+    /// Matches the methods starting with `prefix`.
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        Self::Prefix(prefix.into())
+    }
+
+    /// Matches the methods against `pattern`, such as `debug_trace*`.
+    pub fn glob(pattern: impl Into<String>) -> Self {
+        Self::Glob(pattern.into())
+    }
+
+    /// Whether the rule matches `method`.
+    pub fn matches(&self, method: &str) -> bool {
+        match self {
+            MethodRule::Exact(name) => method == name,
+            MethodRule::Prefix(prefix) => method.starts_with(prefix.as_str()),
+            MethodRule::Glob(pattern) => glob_matches(pattern.as_bytes(), method.as_bytes()),
+        }
+    }
+}
+
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern, and of the text it was matched against.
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more character.
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// The allow and deny rules of a [`MethodFilterLayer`].
+///
+/// A method is allowed when it matches an allow rule, or when there are none, and doesn't
+/// match any deny rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodRules {
+    allow: Vec<MethodRule>,
+    deny: Vec<MethodRule>,
+}
+
+impl MethodRules {
+    /// Creates rules allowing every method.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allows the given namespaces, like geth's `--http.api eth,net`.
+    pub fn namespaces<I>(namespaces: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        namespaces
+            .into_iter()
+            .fold(Self::new(), |rules, namespace| {
+                rules.allow(MethodRule::namespace(namespace))
+            })
+    }
+
+    /// Adds an allow rule.
+    pub fn allow(mut self, rule: MethodRule) -> Self {
+        self.allow.push(rule);
+        self
+    }
+
+    /// Adds a deny rule, which takes precedence over the allow rules.
+    pub fn deny(mut self, rule: MethodRule) -> Self {
+        self.deny.push(rule);
+        self
+    }
+
+    /// Whether `method` is allowed.
+    pub fn is_allowed(&self, method: &str) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|rule| rule.matches(method)))
+            && !self.deny.iter().any(|rule| rule.matches(method))
+    }
+}
+
+/// Replaces the rules of a [`MethodFilterLayer`] and the services it created.
+#[derive(Debug, Clone)]
+pub struct MethodFilterHandle {
+    rules: Arc<watch::Sender<MethodRules>>,
+}
+
+impl MethodFilterHandle {
+    /// The current rules.
+    pub fn rules(&self) -> MethodRules {
+        self.rules.borrow().clone()
+    }
+
+    /// Replaces the rules.
+    pub fn set(&self, rules: MethodRules) {
+        self.rules.send_replace(rules);
+    }
+
+    /// Changes the rules in place, such as to deny a misbehaving method.
+    pub fn update(&self, update: impl FnOnce(MethodRules) -> MethodRules) {
+        self.rules
+            .send_modify(|rules| *rules = update(std::mem::take(rules)));
+    }
+
+    /// Subscribes to the changes of the rules.
+    pub fn subscribe(&self) -> watch::Receiver<MethodRules> {
+        self.rules.subscribe()
+    }
+}
+
+/// A layer that answers calls to filtered methods with a `MethodNotFound` error.
+///
+/// ```ignore
+/// let (layer, handle) = MethodFilterLayer::new(MethodRules::namespaces(["eth", "net"]));
+///
+/// // Later, without restarting:
+/// handle.update(|rules| rules.deny(MethodRule::exact("eth_getLogs")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MethodFilterLayer {
+    rules: watch::Receiver<MethodRules>,
+}
+
+impl MethodFilterLayer {
+    /// Creates a layer filtering methods with `rules`, and the handle replacing them.
+    pub fn new(rules: MethodRules) -> (Self, MethodFilterHandle) {
+        let (sender, receiver) = watch::channel(rules);
+        let handle = MethodFilterHandle {
+            rules: Arc::new(sender),
+        };
+        (Self { rules: receiver }, handle)
+    }
+}
+
+impl<S> Layer<S> for MethodFilterLayer {
+    type Service = MethodFilter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MethodFilter {
+            inner,
+            rules: self.rules.clone(),
+        }
+    }
+}
+
+/// Service produced by [`MethodFilterLayer`].
+#[derive(Debug, Clone)]
+pub struct MethodFilter<S> {
+    inner: S,
+    rules: watch::Receiver<MethodRules>,
+}
+
+impl<S> Service<Request<'static>> for MethodFilter<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let allowed = self.rules.borrow().is_allowed(&request.method);
+        if allowed {
+            Either::Left(self.inner.call(request))
+        } else {
+            Either::Right(ready(Ok(Response::new(
+                ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::MethodNotFound)),
+                request.id,
+            ))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee_types::{ErrorCode, ResponsePayload};
+    use tower::Layer;
+
+    use crate::{
+        filter::{MethodFilterLayer, MethodRule, MethodRules},
+        test_util::{call, echo, request},
+    };
+
+    #[test]
+    fn rules_match_methods() {
+        let rules = MethodRules::namespaces(["eth", "net"])
+            .allow(MethodRule::glob("debug_trace*Call"))
+            .deny(MethodRule::exact("eth_sign"));
+
+        assert!(rules.is_allowed("eth_chainId"));
+        assert!(rules.is_allowed("net_version"));
+        assert!(rules.is_allowed("debug_traceCall"));
+        assert!(rules.is_allowed("debug_traceBlockCall"));
+        assert!(!rules.is_allowed("debug_traceBlock"));
+        assert!(!rules.is_allowed("eth_sign"));
+        assert!(!rules.is_allowed("ethx_chainId"));
+        assert!(!rules.is_allowed("admin_stop"));
+
+        assert!(MethodRule::glob("a?c*").matches("abc"));
+        assert!(!MethodRule::glob("a?c").matches("ac"));
+    }
+
+    #[tokio::test]
+    async fn rules_are_swapped_at_runtime() {
+        let (layer, handle) = MethodFilterLayer::new(MethodRules::new());
+        let svc = layer.layer(echo());
+        let call = || call(&svc, request("eth_getLogs", 1));
+
+        let response = call().await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+
+        handle.update(|rules| rules.deny(MethodRule::exact("eth_getLogs")));
+        let response = call().await.unwrap();
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Error(err) if err.code() == ErrorCode::MethodNotFound.code()
+        ));
+
+        handle.set(MethodRules::new());
+        let response = call().await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+    }
+}
@@ -1,8 +1,28 @@
 #![allow(async_fn_in_trait)]
+pub mod auth;
+pub mod cache;
+pub mod cancel;
 pub mod client;
+pub mod coalesce;
+pub mod cost;
 pub mod error;
+pub mod extract;
+pub mod filter;
+pub mod method;
+pub mod namespace;
+pub mod panic;
+pub mod rate_limit;
+pub mod registry;
 pub mod request;
+pub mod router;
 pub mod server;
+pub mod shutdown;
+pub mod timeout;
+
+mod util;
+
+#[cfg(test)]
+mod test_util;
 
 #[cfg(feature = "ws")]
 pub mod ws_client;
@@ -11,7 +31,14 @@ pub mod types {
     pub use jsonrpsee_types::*;
 }
 
+/// Dependencies of the code generated by `#[rpc]`, so that users don't need to depend on them.
 #[doc(hidden)]
 pub mod __private {
     pub use futures_util;
+    #[cfg(feature = "ws")]
+    pub use jsonrpsee;
+    pub use jsonrpsee_types;
+    pub use serde;
+    pub use serde_json;
+    pub use tower;
 }
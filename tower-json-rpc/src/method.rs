@@ -0,0 +1,129 @@
+//! Per-method services and metadata of the generated server services.
+//!
+//! The `<Trait>ServerBuilder` generated by `#[rpc(server)]` turns every method into a
+//! [`MethodService`] taking a [`MethodCall`] with the typed params of the method, so each
+//! method can be wrapped with its own tower layers before the dispatcher combines them.
+//!
+//! The attributes of the methods are described by [`MethodInfo`], which layers working on
+//! `Request<'static>` can be configured from.
+
+use std::time::Duration;
+
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned};
+use tower::{BoxError, util::BoxCloneSyncService};
+
+use crate::{cost::Cost, extract::RequestParts, rate_limit::RateLimit};
+
+/// A call to a single RPC method.
+#[derive(Debug, Clone)]
+pub struct MethodCall<P> {
+    /// The params of the method, as a tuple in declaration order.
+    pub params: P,
+    /// The parts of the request, read by the extractor arguments of the method.
+    pub parts: RequestParts,
+}
+
+/// A boxed service handling the calls of one RPC method and returning its result.
+pub type MethodService<P, R> = BoxCloneSyncService<MethodCall<P>, R, BoxError>;
+
+/// Converts the error of a [`MethodService`] into the error object of the response.
+///
+/// Errors returned by the handler are passed through, other errors, such as the ones of the
+/// layers wrapping the method, become internal errors.
+pub fn into_error_object(err: BoxError) -> ErrorObjectOwned {
+    match err.downcast::<ErrorObjectOwned>() {
+        Ok(err) => *err,
+        Err(err) => ErrorObjectOwned::owned(
+            ErrorCode::InternalError.code(),
+            ErrorCode::InternalError.message(),
+            Some(err.to_string()),
+        ),
+    }
+}
+
+/// The attributes of an RPC method declared with `#[method(...)]`.
+///
+/// Listed by the `RpcMethods` impl of the generated server services.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MethodInfo {
+    /// Full name of the method, including the namespace.
+    pub name: &'static str,
+    /// Other names of the method.
+    pub aliases: &'static [&'static str],
+    /// The `timeout` of the method, or the trait-level default.
+    pub timeout: Option<Duration>,
+    /// The role required to call the method, or the trait-level default.
+    pub requires: Option<&'static str>,
+    /// The `rate_limit` of each caller of the method, or the trait-level default.
+    pub rate_limit: Option<RateLimit>,
+    /// The `cost` of a call to the method, or the trait-level default.
+    pub cost: Option<Cost>,
+    /// Whether the results of the method can be cached, as declared with `cacheable`.
+    pub cacheable: bool,
+    /// How long the results of the method can be cached.
+    pub ttl: Option<Duration>,
+}
+
+impl MethodInfo {
+    /// Describes the method `name`, without any attribute.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            timeout: None,
+            requires: None,
+            rate_limit: None,
+            cost: None,
+            cacheable: false,
+            ttl: None,
+        }
+    }
+
+    /// Sets the aliases of the method.
+    pub const fn with_aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Sets the timeout of the method.
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the role required to call the method.
+    pub const fn with_requires(mut self, role: &'static str) -> Self {
+        self.requires = Some(role);
+        self
+    }
+
+    /// Sets the rate limit of each caller of the method.
+    pub const fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Sets the cost of a call to the method.
+    pub const fn with_cost(mut self, cost: Cost) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+
+    /// Marks the results of the method as cacheable.
+    pub const fn with_cacheable(mut self) -> Self {
+        self.cacheable = true;
+        self
+    }
+
+    /// Sets how long the results of the method can be cached.
+    pub const fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The name and the aliases of the method.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + use<> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
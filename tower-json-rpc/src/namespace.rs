@@ -0,0 +1,189 @@
+//! Mounting an RPC service under a method-name prefix chosen at runtime.
+//!
+//! [`NamespaceLayer`] rewrites the method of every request before the inner service sees it:
+//! it strips the prefix that clients use and adds the prefix that the inner service expects.
+//! The same rewrite is applied in reverse to subscription notifications with
+//! [`NamespaceLayer::notification`].
+
+use std::{
+    future::{Ready, ready},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::future::Either;
+use jsonrpsee_types::{
+    ErrorCode, ErrorObjectOwned, Notification, Request, Response, ResponsePayload,
+};
+use serde_json::Value;
+use tower::{Layer, Service};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Prefixes {
+    /// Prefix of the method names called by the clients, removed before the inner service.
+    strip: Option<String>,
+    /// Prefix of the method names served by the inner service.
+    add: Option<String>,
+}
+
+/// A layer that maps the method names called by clients to the ones served by the inner
+/// service.
+///
+/// ```ignore
+/// // Serve `say_hello` of a `#[rpc(namespace = "say")]` API as `v2.say.hello`.
+/// let layer = NamespaceLayer::new().strip("v2.say", ".").add("say", "_");
+/// ```
+///
+/// Requests whose method doesn't start with the stripped prefix are answered with a
+/// `MethodNotFound` error.
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceLayer {
+    prefixes: Arc<Prefixes>,
+}
+
+impl NamespaceLayer {
+    /// Creates a layer that doesn't rewrite method names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes `prefix` and `separator` from the called method names.
+    pub fn strip(mut self, prefix: impl Into<String>, separator: impl AsRef<str>) -> Self {
+        Arc::make_mut(&mut self.prefixes).strip = Some(prefix.into() + separator.as_ref());
+        self
+    }
+
+    /// Adds `prefix` and `separator` to the method names seen by the inner service.
+    pub fn add(mut self, prefix: impl Into<String>, separator: impl AsRef<str>) -> Self {
+        Arc::make_mut(&mut self.prefixes).add = Some(prefix.into() + separator.as_ref());
+        self
+    }
+
+    /// Maps a method called by clients to the method of the inner service, if the method is
+    /// in the namespace.
+    pub fn inner_method(&self, method: &str) -> Option<String> {
+        let method = match &self.prefixes.strip {
+            Some(prefix) => method.strip_prefix(prefix.as_str())?,
+            None => method,
+        };
+        Some(format!(
+            "{}{method}",
+            self.prefixes.add.as_deref().unwrap_or_default()
+        ))
+    }
+
+    /// Maps a method of the inner service to the method called by clients, if the method is
+    /// in the namespace.
+    pub fn outer_method(&self, method: &str) -> Option<String> {
+        let method = match &self.prefixes.add {
+            Some(prefix) => method.strip_prefix(prefix.as_str())?,
+            None => method,
+        };
+        Some(format!(
+            "{}{method}",
+            self.prefixes.strip.as_deref().unwrap_or_default()
+        ))
+    }
+
+    /// Renames a subscription notification sent by the inner service to the namespace of the
+    /// clients. Notifications outside of the namespace are left untouched.
+    pub fn notification<'a, T>(
+        &self,
+        mut notification: Notification<'a, T>,
+    ) -> Notification<'a, T> {
+        if let Some(method) = self.outer_method(&notification.method) {
+            notification.method = method.into();
+        }
+        notification
+    }
+}
+
+impl<S> Layer<S> for NamespaceLayer {
+    type Service = NamespaceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NamespaceService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`NamespaceLayer`].
+#[derive(Debug, Clone)]
+pub struct NamespaceService<S> {
+    inner: S,
+    layer: NamespaceLayer,
+}
+
+impl<S> Service<Request<'static>> for NamespaceService<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<'static>) -> Self::Future {
+        match self.layer.inner_method(&request.method) {
+            Some(method) => {
+                request.method = method.into();
+                Either::Left(self.inner.call(request))
+            }
+            None => Either::Right(ready(Ok(Response::new(
+                ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::MethodNotFound)),
+                request.id,
+            )))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee_types::{ErrorCode, Id, Notification, Request, Response, ResponsePayload};
+    use serde_json::Value;
+    use tower::{Layer, ServiceExt, service_fn};
+
+    use crate::namespace::NamespaceLayer;
+
+    #[tokio::test]
+    async fn rewrites_request_methods() {
+        let layer = NamespaceLayer::new().strip("v2.say", ".").add("say", "_");
+        let svc = layer.layer(service_fn(|req: Request<'static>| async move {
+            Ok::<_, std::convert::Infallible>(Response::new(
+                ResponsePayload::success(Value::from(req.method.to_string())),
+                req.id,
+            ))
+        }));
+
+        let request = Request::owned("v2.say.hello".to_string(), None, Id::Number(1));
+        let response = svc.clone().oneshot(request).await.unwrap();
+        assert!(
+            matches!(response.payload, ResponsePayload::Success(value) if *value == "say_hello")
+        );
+
+        let request = Request::owned("say_hello".to_string(), None, Id::Number(2));
+        let response = svc.oneshot(request).await.unwrap();
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Error(err) if err.code() == ErrorCode::MethodNotFound.code()
+        ));
+    }
+
+    #[test]
+    fn rewrites_notifications_back() {
+        let layer = NamespaceLayer::new().strip("eth", "_");
+        assert_eq!(
+            layer.inner_method("eth_subscribe").as_deref(),
+            Some("subscribe")
+        );
+        assert_eq!(layer.inner_method("debug_subscribe"), None);
+
+        let notification = layer.notification(Notification::new("subscription".into(), ()));
+        assert_eq!(notification.method, "eth_subscription");
+    }
+}
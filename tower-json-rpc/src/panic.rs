@@ -0,0 +1,204 @@
+//! Answering calls whose handler panicked.
+//!
+//! Without [`CatchPanicLayer`], a panic in a handler unwinds through the service and tears
+//! down the HTTP connection or the WS session carrying the call. With it, the call is
+//! answered with a `-32603 Internal error` carrying the request id, and the panic can be
+//! reported through a hook. The panic message isn't sent to the client.
+
+use std::{
+    any::Any,
+    fmt,
+    future::ready,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_util::FutureExt;
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::{Layer, Service};
+
+use crate::server::BoxFuture;
+
+/// A panic caught while serving a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panic {
+    /// The called method.
+    pub method: String,
+    /// The message of the panic, when it is a string.
+    pub message: String,
+}
+
+type Hook = Arc<dyn Fn(&Panic) + Send + Sync>;
+
+/// A layer that answers calls panicking in the inner service with an internal error.
+///
+/// ```ignore
+/// let layer = CatchPanicLayer::new()
+///     .on_panic(|panic| tracing::error!(method = panic.method, "{}", panic.message));
+/// ```
+#[derive(Clone, Default)]
+pub struct CatchPanicLayer {
+    hook: Option<Hook>,
+}
+
+impl CatchPanicLayer {
+    /// Creates a layer that doesn't report panics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `hook` with every caught panic.
+    pub fn on_panic(mut self, hook: impl Fn(&Panic) + Send + Sync + 'static) -> Self {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+}
+
+impl fmt::Debug for CatchPanicLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CatchPanicLayer")
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
+
+impl<S> Layer<S> for CatchPanicLayer {
+    type Service = CatchPanic<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CatchPanic {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`CatchPanicLayer`].
+#[derive(Debug, Clone)]
+pub struct CatchPanic<S> {
+    inner: S,
+    layer: CatchPanicLayer,
+}
+
+impl<S> Service<Request<'static>> for CatchPanic<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let method = request.method.to_string();
+        let id = request.id.clone();
+        let hook = self.layer.hook.clone();
+        let answer = move |payload: Box<dyn Any + Send>| {
+            let panic = Panic {
+                method,
+                message: panic_message(payload.as_ref()),
+            };
+            if let Some(hook) = hook {
+                hook(&panic);
+            }
+            Ok(Response::new(
+                ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::InternalError)),
+                id,
+            ))
+        };
+
+        // The inner service may panic while creating the future, or while polling it.
+        match catch_unwind(AssertUnwindSafe(|| self.inner.call(request))) {
+            Ok(future) => {
+                Box::pin(
+                    AssertUnwindSafe(future)
+                        .catch_unwind()
+                        .map(move |result| match result {
+                            Ok(result) => result,
+                            Err(payload) => answer(payload),
+                        }),
+                )
+            }
+            Err(payload) => Box::pin(ready(answer(payload))),
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use jsonrpsee_types::{ErrorCode, Id, Request, Response, ResponsePayload};
+    use serde_json::Value;
+    use tower::{Layer, service_fn};
+
+    use crate::{
+        panic::{CatchPanicLayer, Panic},
+        test_util::{call, request},
+    };
+
+    #[tokio::test]
+    async fn panics_become_internal_errors() {
+        let panics = Arc::new(Mutex::new(Vec::new()));
+        let reported = panics.clone();
+        let svc = CatchPanicLayer::new()
+            .on_panic(move |panic| reported.lock().unwrap().push(panic.clone()))
+            .layer(service_fn(|req: Request<'static>| {
+                if req.method == "eth_call" {
+                    panic!("call panicked");
+                }
+                async move {
+                    if req.method == "eth_getLogs" {
+                        panic!("{} panicked", req.method);
+                    }
+                    Ok::<_, std::convert::Infallible>(Response::new(
+                        ResponsePayload::success(Value::Null),
+                        req.id,
+                    ))
+                }
+            }));
+
+        for (method, id) in [("eth_call", 1), ("eth_getLogs", 2)] {
+            let response = call(&svc, request(method, id)).await.unwrap();
+            assert_eq!(response.id, Id::Number(id));
+            assert!(matches!(
+                response.payload,
+                ResponsePayload::Error(err) if err.code() == ErrorCode::InternalError.code()
+            ));
+        }
+
+        let response = call(&svc, request("eth_chainId", 3)).await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+
+        assert_eq!(
+            *panics.lock().unwrap(),
+            [
+                Panic {
+                    method: "eth_call".to_string(),
+                    message: "call panicked".to_string(),
+                },
+                Panic {
+                    method: "eth_getLogs".to_string(),
+                    message: "eth_getLogs panicked".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Rate limiting of JSON-RPC calls per caller and per method.
+//!
+//! Unlike `tower::limit::RateLimit`, which applies backpressure to every request of a service,
+//! [`JsonRpcRateLimitLayer`] keeps a token bucket per caller and method, and answers the calls
+//! over the limit with an error telling the caller when to retry.
+
+use std::{
+    collections::HashMap,
+    future::{Ready, ready},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::Either;
+use jsonrpsee_types::{ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::{Layer, Service};
+
+use crate::{auth::Principal, method::MethodInfo};
+
+/// Error code of the rate limit error.
+pub const RATE_LIMITED_CODE: i32 = -32005;
+
+/// The error returned when a call is over the limit: `-32005 rate limit exceeded`, with the
+/// time to wait before retrying as `{ "retry_after_ms": ... }` data.
+pub fn rate_limited_error(retry_after: Duration) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        RATE_LIMITED_CODE,
+        "rate limit exceeded",
+        Some(serde_json::json!({
+            "retry_after_ms": u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX)
+        })),
+    )
+}
+
+/// A quota of at most `requests` calls every `period`.
+///
+/// Calls are allowed in bursts of up to `requests`, after which the quota refills steadily
+/// over `period`. A quota of 0 requests rejects every call, and a zero `period` doesn't limit
+/// the calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Number of calls allowed during `period`.
+    pub requests: u32,
+    /// Duration over which the calls are counted.
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// Allows `requests` calls every `period`.
+    pub const fn new(requests: u32, period: Duration) -> Self {
+        Self { requests, period }
+    }
+
+    fn per_second(&self) -> f64 {
+        f64::from(self.requests) / self.period.as_secs_f64()
+    }
+}
+
+/// Identifies the caller of `request` by its [`Principal`], or else by its remote IP address.
+///
+/// Requests without either share the limits of anonymous callers.
+pub fn caller_identity(request: &Request<'static>) -> Option<String> {
+    let extensions = &request.extensions;
+    if let Some(principal) = extensions.get::<Principal>() {
+        return Some(principal.id.clone());
+    }
+    extensions
+        .get::<SocketAddr>()
+        .copied()
+        .or_else(|| {
+            extensions
+                .get::<axum::extract::ConnectInfo<SocketAddr>>()
+                .map(|info| info.0)
+        })
+        .map(|addr| addr.ip().to_string())
+}
+
+#[derive(Debug, Clone)]
+struct Limits {
+    default: Option<RateLimit>,
+    /// Limits by method name or alias, along with the name the calls are counted under.
+    methods: HashMap<String, (String, RateLimit)>,
+    caller: fn(&Request<'static>) -> Option<String>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.requests),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let refilled = if limit.period.is_zero() {
+            f64::INFINITY
+        } else {
+            now.duration_since(self.updated).as_secs_f64() * limit.per_second()
+        };
+        self.tokens = (self.tokens + refilled).min(f64::from(limit.requests));
+        self.updated = now;
+    }
+
+    /// Takes a token, or returns how long to wait for the next one.
+    fn acquire(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            // A quota of 0 requests never refills, so the wait saturates.
+            let wait = (1.0 - self.tokens) / limit.per_second();
+            Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<(Option<String>, String), (RateLimit, Bucket)>,
+    /// Number of buckets above which the refilled ones are dropped.
+    prune_at: usize,
+}
+
+impl Buckets {
+    fn acquire(
+        &mut self,
+        key: (Option<String>, String),
+        limit: RateLimit,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        if self.buckets.len() >= self.prune_at {
+            self.buckets.retain(|_, (limit, bucket)| {
+                bucket.refill(*limit, now);
+                bucket.tokens < f64::from(limit.requests)
+            });
+            self.prune_at = (self.buckets.len() * 2).max(1024);
+        }
+
+        let (_, bucket) = self
+            .buckets
+            .entry(key)
+            .or_insert_with(|| (limit, Bucket::full(limit, now)));
+        bucket.acquire(limit, now)
+    }
+}
+
+/// A layer that limits the rate of calls of each caller to each method.
+///
+/// ```ignore
+/// let layer = JsonRpcRateLimitLayer::new()
+///     .default_limit(RateLimit::new(100, Duration::from_secs(1)))
+///     .methods(<SayService as RpcMethods>::INFO)
+///     .method("eth_getLogs", RateLimit::new(10, Duration::from_secs(60)));
+/// ```
+///
+/// Clones of the layer, and the services it creates, share the same buckets.
+#[derive(Debug, Clone)]
+pub struct JsonRpcRateLimitLayer {
+    limits: Arc<Limits>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl Default for JsonRpcRateLimitLayer {
+    fn default() -> Self {
+        Self {
+            limits: Arc::new(Limits {
+                default: None,
+                methods: HashMap::new(),
+                caller: caller_identity,
+            }),
+            buckets: Arc::default(),
+        }
+    }
+}
+
+impl JsonRpcRateLimitLayer {
+    /// Creates a layer without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the limit of the methods without their own limit.
+    pub fn default_limit(mut self, limit: RateLimit) -> Self {
+        Arc::make_mut(&mut self.limits).default = Some(limit);
+        self
+    }
+
+    /// Sets the limit of the method `name`.
+    pub fn method(mut self, name: impl Into<String>, limit: RateLimit) -> Self {
+        let name = name.into();
+        Arc::make_mut(&mut self.limits)
+            .methods
+            .insert(name.clone(), (name, limit));
+        self
+    }
+
+    /// Sets the limits declared with `#[method(rate_limit = "...")]`. Calls to the aliases of
+    /// a method are counted along with the calls to the method.
+    pub fn methods(mut self, methods: &[MethodInfo]) -> Self {
+        let limits = Arc::make_mut(&mut self.limits);
+        for info in methods {
+            if let Some(limit) = info.rate_limit {
+                limits.methods.extend(
+                    info.names()
+                        .map(|name| (name.to_string(), (info.name.to_string(), limit))),
+                );
+            }
+        }
+        self
+    }
+
+    /// Sets how callers are identified, instead of [`caller_identity`].
+    pub fn caller(mut self, caller: fn(&Request<'static>) -> Option<String>) -> Self {
+        Arc::make_mut(&mut self.limits).caller = caller;
+        self
+    }
+}
+
+impl<S> Layer<S> for JsonRpcRateLimitLayer {
+    type Service = JsonRpcRateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JsonRpcRateLimit {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`JsonRpcRateLimitLayer`].
+#[derive(Debug, Clone)]
+pub struct JsonRpcRateLimit<S> {
+    inner: S,
+    layer: JsonRpcRateLimitLayer,
+}
+
+impl<S> Service<Request<'static>> for JsonRpcRateLimit<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let limits = &self.layer.limits;
+        let limit = match limits.methods.get(request.method.as_ref()) {
+            Some((method, limit)) => Some((method.clone(), *limit)),
+            None => limits
+                .default
+                .map(|limit| (request.method.to_string(), limit)),
+        };
+
+        let acquired = match limit {
+            Some((method, limit)) => {
+                let key = ((limits.caller)(&request), method);
+                let mut buckets = self.layer.buckets.lock().unwrap();
+                buckets.acquire(key, limit, Instant::now())
+            }
+            None => Ok(()),
+        };
+
+        match acquired {
+            Ok(()) => Either::Left(self.inner.call(request)),
+            Err(retry_after) => Either::Right(ready(Ok(Response::new(
+                ResponsePayload::error(rate_limited_error(retry_after)),
+                request.id,
+            )))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use jsonrpsee_types::{Id, ResponsePayload};
+    use serde_json::Value;
+    use tower::Layer;
+
+    use crate::{
+        auth::Principal,
+        rate_limit::{Bucket, JsonRpcRateLimitLayer, RATE_LIMITED_CODE, RateLimit},
+        test_util::{call, echo, request},
+    };
+
+    #[tokio::test]
+    async fn limits_each_caller_and_method() {
+        let svc = JsonRpcRateLimitLayer::new()
+            .method("eth_getLogs", RateLimit::new(2, Duration::from_secs(60)))
+            .layer(echo());
+
+        let call = |method: &str, caller: &str| {
+            let mut request = request(method, 5);
+            request.extensions.insert(Principal::new(caller));
+            call(&svc, request)
+        };
+
+        for _ in 0..2 {
+            let response = call("eth_getLogs", "ada").await.unwrap();
+            assert!(matches!(response.payload, ResponsePayload::Success(_)));
+        }
+
+        let response = call("eth_getLogs", "ada").await.unwrap();
+        assert_eq!(response.id, Id::Number(5));
+        let ResponsePayload::Error(err) = response.payload else {
+            panic!("expected a rate limit error");
+        };
+        assert_eq!(err.code(), RATE_LIMITED_CODE);
+        let data: Value = serde_json::from_str(err.data().unwrap().get()).unwrap();
+        assert!(data["retry_after_ms"].as_u64().unwrap() > 0);
+
+        let response = call("eth_getLogs", "bob").await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+
+        let response = call("eth_chainId", "ada").await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+    }
+
+    #[test]
+    fn zero_limits_do_not_panic() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(1);
+
+        for limit in [
+            RateLimit::new(0, Duration::from_secs(1)),
+            RateLimit::new(0, Duration::ZERO),
+        ] {
+            let mut bucket = Bucket::full(limit, now);
+            assert_eq!(bucket.acquire(limit, now), Err(Duration::MAX));
+            assert_eq!(bucket.acquire(limit, later), Err(Duration::MAX));
+        }
+
+        let unlimited = RateLimit::new(1, Duration::ZERO);
+        let mut bucket = Bucket::full(unlimited, now);
+        for _ in 0..3 {
+            assert_eq!(bucket.acquire(unlimited, now), Ok(()));
+        }
+        assert_eq!(bucket.acquire(unlimited, later), Ok(()));
+    }
+}
@@ -0,0 +1,273 @@
+//! Methods registered at runtime, next to the ones generated by `#[rpc]`.
+//!
+//! A [`MethodRegistry`] maps method names to async closures or services taking typed params.
+//! It implements `Service<Request<'static>>`, so it can be the inner service of a generated
+//! `<Trait>ServerLayer` or be mounted in an [`RpcRouter`](crate::router::RpcRouter).
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::Future,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll},
+};
+
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+use tower::{Service, ServiceExt};
+
+use crate::server::BoxFuture;
+
+type Handler =
+    Arc<dyn Fn(Request<'static>) -> BoxFuture<ResponsePayload<'static, Value>> + Send + Sync>;
+
+#[derive(Clone)]
+struct Entry {
+    /// Name the method was registered with, shared by its aliases.
+    method: Arc<str>,
+    handler: Handler,
+}
+
+/// Returned by [`MethodRegistry`] when registering a name fails.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    #[error("method `{0}` is already registered")]
+    AlreadyRegistered(String),
+    #[error("method `{0}` is not registered")]
+    NotRegistered(String),
+}
+
+/// A service calling methods registered at runtime.
+///
+/// Params are parsed as `P` with [`Params::parse`](jsonrpsee_types::Params::parse), so a tuple
+/// reads positional params and a struct reads named params. Unknown methods are answered with
+/// a `MethodNotFound` error.
+///
+/// Clones share the same methods, so methods can be added or removed while the service runs.
+#[derive(Clone, Default)]
+pub struct MethodRegistry {
+    methods: Arc<RwLock<HashMap<String, Entry>>>,
+}
+
+impl MethodRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an async closure as the method `name`.
+    pub fn register<P, R, F, Fut>(
+        &self,
+        name: impl Into<String>,
+        method: F,
+    ) -> Result<(), RegistryError>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, ErrorObjectOwned>> + Send + 'static,
+    {
+        let handler: Handler =
+            Arc::new(
+                move |request: Request<'static>| match request.params().parse::<P>() {
+                    Ok(params) => {
+                        let fut = method(params);
+                        Box::pin(async move { into_payload(fut.await) })
+                    }
+                    Err(err) => Box::pin(async move { ResponsePayload::error(err) }),
+                },
+            );
+        self.insert(name.into(), handler)
+    }
+
+    /// Registers a service taking typed params, such as a `BoxCloneService`, as the method `name`.
+    pub fn register_service<P, R, S>(
+        &self,
+        name: impl Into<String>,
+        service: S,
+    ) -> Result<(), RegistryError>
+    where
+        P: DeserializeOwned + Send + 'static,
+        R: Serialize,
+        S: Service<P, Response = R, Error = ErrorObjectOwned> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        let service = Mutex::new(service);
+        self.register(name, move |params: P| {
+            let service = service.lock().unwrap().clone();
+            service.oneshot(params)
+        })
+    }
+
+    /// Registers `alias` as another name of the registered method `method`.
+    pub fn alias(&self, alias: impl Into<String>, method: &str) -> Result<(), RegistryError> {
+        let mut methods = self.methods.write().unwrap();
+        let entry = methods
+            .get(method)
+            .cloned()
+            .ok_or_else(|| RegistryError::NotRegistered(method.to_string()))?;
+
+        let alias = alias.into();
+        if methods.contains_key(&alias) {
+            return Err(RegistryError::AlreadyRegistered(alias));
+        }
+        methods.insert(alias, entry);
+        Ok(())
+    }
+
+    /// Removes the method `name` along with its aliases, or only `name` if it is an alias.
+    ///
+    /// Returns `false` if nothing was registered under `name`.
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut methods = self.methods.write().unwrap();
+        let Some(entry) = methods.remove(name) else {
+            return false;
+        };
+        if *entry.method == *name {
+            methods.retain(|_, other| other.method != entry.method);
+        }
+        true
+    }
+
+    /// Whether a method or alias is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.methods.read().unwrap().contains_key(name)
+    }
+
+    /// Names of the registered methods and aliases.
+    pub fn methods(&self) -> Vec<String> {
+        self.methods.read().unwrap().keys().cloned().collect()
+    }
+
+    fn insert(&self, name: String, handler: Handler) -> Result<(), RegistryError> {
+        let mut methods = self.methods.write().unwrap();
+        if methods.contains_key(&name) {
+            return Err(RegistryError::AlreadyRegistered(name));
+        }
+        let entry = Entry {
+            method: Arc::from(name.as_str()),
+            handler,
+        };
+        methods.insert(name, entry);
+        Ok(())
+    }
+}
+
+impl Service<Request<'static>> for MethodRegistry {
+    type Response = Response<'static, Value>;
+    type Error = Infallible;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let handler = self
+            .methods
+            .read()
+            .unwrap()
+            .get(request.method.as_ref())
+            .map(|entry| entry.handler.clone());
+        let id = request.id.clone();
+
+        let payload = match handler {
+            Some(handler) => handler(request),
+            None => Box::pin(async {
+                ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::MethodNotFound))
+            }),
+        };
+        Box::pin(async move { Ok(Response::new(payload.await, id)) })
+    }
+}
+
+fn into_payload<R: Serialize>(
+    result: Result<R, ErrorObjectOwned>,
+) -> ResponsePayload<'static, Value> {
+    match result.and_then(|value| {
+        serde_json::to_value(value).map_err(|err| {
+            ErrorObjectOwned::owned(
+                ErrorCode::InternalError.code(),
+                ErrorCode::InternalError.message(),
+                Some(err.to_string()),
+            )
+        })
+    }) {
+        Ok(value) => ResponsePayload::success(value),
+        Err(err) => ResponsePayload::error(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Id, Request, ResponsePayload};
+    use tower::{ServiceExt, service_fn, util::BoxCloneService};
+
+    use crate::registry::{MethodRegistry, RegistryError};
+
+    async fn call(
+        registry: &MethodRegistry,
+        method: &str,
+        params: serde_json::Value,
+    ) -> ResponsePayload<'static, serde_json::Value> {
+        let params = serde_json::value::to_raw_value(&params).unwrap();
+        let request = Request::owned(method.to_string(), Some(params), Id::Number(1));
+        registry.clone().oneshot(request).await.unwrap().payload
+    }
+
+    #[tokio::test]
+    async fn calls_registered_methods() {
+        let registry = MethodRegistry::new();
+        registry
+            .register("plugin_add", |(a, b): (u64, u64)| async move {
+                Ok::<_, ErrorObjectOwned>(a + b)
+            })
+            .unwrap();
+        registry
+            .register_service(
+                "plugin_echo",
+                BoxCloneService::new(service_fn(
+                    |(message,): (String,)| async move { Ok(message) },
+                )),
+            )
+            .unwrap();
+        registry.alias("plugin_sum", "plugin_add").unwrap();
+
+        let payload = call(&registry, "plugin_sum", serde_json::json!([1, 2])).await;
+        assert!(matches!(payload, ResponsePayload::Success(value) if *value == 3));
+
+        let payload = call(&registry, "plugin_echo", serde_json::json!(["hi"])).await;
+        assert!(matches!(payload, ResponsePayload::Success(value) if *value == "hi"));
+
+        let payload = call(&registry, "plugin_add", serde_json::json!(["one"])).await;
+        assert!(
+            matches!(payload, ResponsePayload::Error(err) if err.code() == ErrorCode::InvalidParams.code())
+        );
+    }
+
+    #[tokio::test]
+    async fn unregisters_methods_and_aliases() {
+        let registry = MethodRegistry::new();
+        registry
+            .register("plugin_ping", |(): ()| async {
+                Ok::<_, ErrorObjectOwned>("pong")
+            })
+            .unwrap();
+        registry.alias("ping", "plugin_ping").unwrap();
+
+        assert_eq!(
+            registry.register("ping", |(): ()| async { Ok::<_, ErrorObjectOwned>("pong") }),
+            Err(RegistryError::AlreadyRegistered("ping".to_string()))
+        );
+
+        assert!(registry.unregister("plugin_ping"));
+        assert!(!registry.contains("ping"));
+
+        let payload = call(&registry, "plugin_ping", serde_json::Value::Null).await;
+        assert!(
+            matches!(payload, ResponsePayload::Error(err) if err.code() == ErrorCode::MethodNotFound.code())
+        );
+    }
+}
@@ -8,9 +8,12 @@ use serde_json::Value;
 
 use crate::{
     error::JsonRpcError,
-    server::{ServerRequest, ServerResponse},
+    server::{Deprecation, ServerRequest, ServerResponse},
 };
 
+/// The `Deprecation` response header, set when a deprecated RPC method was called.
+const DEPRECATION: header::HeaderName = header::HeaderName::from_static("deprecation");
+
 impl<B> ServerRequest for http::Request<B>
 where
     B: Body<Data = Bytes> + Send + 'static,
@@ -28,11 +31,15 @@ where
         >,
     > {
         Box::pin(async move {
-            let bytes = self.collect().await.map_err(Into::into)?.to_bytes();
+            let (parts, body) = self.into_parts();
+            let bytes = body.collect().await.map_err(Into::into)?.to_bytes();
             let request: Request<'_> = serde_json::from_slice(&bytes)?;
             let params = request.params.map(|params| params.into_owned());
-            let request =
+            let mut request =
                 Request::owned(request.method.into_owned(), params, request.id.into_owned());
+            // Keep the transport extensions and headers around for the extractors.
+            request.extensions = parts.extensions;
+            request.extensions.insert(parts.headers);
             Ok(request)
         })
     }
@@ -62,11 +69,17 @@ impl ServerResponse for http::Response<Full<Bytes>> {
             let json = serde_json::to_vec(&response).map_err(JsonRpcError::from)?;
             let body = Full::new(Bytes::from(json));
 
-            http::Response::builder()
+            let mut builder = http::Response::builder()
                 .status(200)
-                .header(header::CONTENT_TYPE, "application/json")
-                .body(body)
-                .map_err(Into::<JsonRpcError>::into)
+                .header(header::CONTENT_TYPE, "application/json");
+            if let Some(deprecation) = response.extensions().get::<Deprecation>() {
+                if let Some(timestamp) = deprecation.since_timestamp() {
+                    builder = builder.header(DEPRECATION, format!("@{timestamp}"));
+                }
+                builder = builder.extension(deprecation.clone());
+            }
+
+            builder.body(body).map_err(Into::<JsonRpcError>::into)
         })
     }
 }
@@ -0,0 +1,282 @@
+//! Routing of JSON-RPC requests between the services of several RPC APIs.
+//!
+//! Services generated by `#[rpc(server)]` implement [`RpcMethods`], so they can be added to an
+//! [`RpcRouter`] without listing their methods. Any other `Service<Request<'static>>` can be
+//! routed with an explicit list of method names.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::{Ready, ready},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use jsonrpsee_types::{ErrorCode, ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use thiserror::Error;
+use tower::{
+    Layer, Service, ServiceExt,
+    util::{BoxCloneSyncService, Oneshot},
+};
+
+use crate::{error::JsonRpcError, method::MethodInfo, namespace::NamespaceLayer};
+
+/// RPC method names served by a service, including aliases.
+///
+/// Implemented by the `<Trait>ServerService` generated by `#[rpc(server)]`.
+pub trait RpcMethods {
+    /// Full names of the served methods and subscriptions, including the namespace.
+    const METHODS: &'static [&'static str];
+
+    /// The attributes of the served methods.
+    const INFO: &'static [MethodInfo];
+}
+
+/// A service answering every request with a `MethodNotFound` error.
+///
+/// This is the default fallback of [`RpcRouter`], and a convenient inner service for the
+/// generated server layers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MethodNotFound;
+
+impl Service<Request<'static>> for MethodNotFound {
+    type Response = Response<'static, Value>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        ready(Ok(Response::new(
+            ResponsePayload::error(ErrorObjectOwned::from(ErrorCode::MethodNotFound)),
+            request.id,
+        )))
+    }
+}
+
+/// Returned by [`RpcRouterBuilder::build`] when a method is routed to more than one service.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("method `{0}` is routed to more than one service")]
+pub struct DuplicateMethod(pub String);
+
+type RouteService = BoxCloneSyncService<Request<'static>, Response<'static, Value>, JsonRpcError>;
+
+/// Builder of an [`RpcRouter`].
+pub struct RpcRouterBuilder {
+    routes: Vec<(Vec<String>, RouteService)>,
+    fallback: RouteService,
+}
+
+impl RpcRouterBuilder {
+    /// Routes the methods of a generated server service to it.
+    pub fn service<S>(self, service: S) -> Self
+    where
+        S: RpcMethods
+            + Service<Request<'static>, Response = Response<'static, Value>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Error: Into<JsonRpcError>,
+        S::Future: Send + 'static,
+    {
+        self.route(S::METHODS.iter().copied(), service)
+    }
+
+    /// Routes the methods of a generated server service to it, renamed by `namespace`.
+    ///
+    /// Methods that are not in the namespace of `namespace` are not routed.
+    pub fn namespaced<S>(self, namespace: NamespaceLayer, service: S) -> Self
+    where
+        S: RpcMethods
+            + Service<Request<'static>, Response = Response<'static, Value>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Error: Into<JsonRpcError> + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        let methods: Vec<_> = S::METHODS
+            .iter()
+            .filter_map(|method| namespace.outer_method(method))
+            .collect();
+        self.route(methods, namespace.layer(service))
+    }
+
+    /// Routes the given method names to `service`.
+    pub fn route<I, S>(mut self, methods: I, service: S) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+        S: Service<Request<'static>, Response = Response<'static, Value>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Error: Into<JsonRpcError>,
+        S::Future: Send + 'static,
+    {
+        let methods = methods.into_iter().map(Into::into).collect();
+        self.routes.push((methods, boxed(service)));
+        self
+    }
+
+    /// Sets the service called for unknown methods, instead of [`MethodNotFound`].
+    pub fn fallback<S>(mut self, service: S) -> Self
+    where
+        S: Service<Request<'static>, Response = Response<'static, Value>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Error: Into<JsonRpcError>,
+        S::Future: Send + 'static,
+    {
+        self.fallback = boxed(service);
+        self
+    }
+
+    /// Builds the router, failing if a method is routed to more than one service.
+    pub fn build(self) -> Result<RpcRouter, DuplicateMethod> {
+        let mut routes = HashMap::new();
+        let mut services = Vec::with_capacity(self.routes.len());
+
+        for (index, (methods, service)) in self.routes.into_iter().enumerate() {
+            for method in methods {
+                if routes.contains_key(&method) {
+                    return Err(DuplicateMethod(method));
+                }
+                routes.insert(method, index);
+            }
+            services.push(service);
+        }
+
+        Ok(RpcRouter {
+            routes: Arc::new(routes),
+            services,
+            fallback: self.fallback,
+        })
+    }
+}
+
+/// A service dispatching JSON-RPC requests to other services by method name.
+///
+/// ```ignore
+/// let router = RpcRouter::builder()
+///     .service(SayServerLayer::new(SayImpl).layer(MethodNotFound))
+///     .service(AdminServerLayer::new(AdminImpl).layer(MethodNotFound))
+///     .build()?;
+/// ```
+#[derive(Clone)]
+pub struct RpcRouter {
+    routes: Arc<HashMap<String, usize>>,
+    services: Vec<RouteService>,
+    fallback: RouteService,
+}
+
+impl RpcRouter {
+    /// Creates a builder with no routes and a [`MethodNotFound`] fallback.
+    pub fn builder() -> RpcRouterBuilder {
+        RpcRouterBuilder {
+            routes: Vec::new(),
+            fallback: boxed(MethodNotFound),
+        }
+    }
+
+    /// Names of the routed methods.
+    pub fn methods(&self) -> impl Iterator<Item = &str> {
+        self.routes.keys().map(String::as_str)
+    }
+}
+
+impl Service<Request<'static>> for RpcRouter {
+    type Response = Response<'static, Value>;
+    type Error = JsonRpcError;
+    type Future = Oneshot<RouteService, Request<'static>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness is checked on the routed service once the method is known.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let service = match self.routes.get(request.method.as_ref()) {
+            Some(&index) => self.services[index].clone(),
+            None => self.fallback.clone(),
+        };
+        service.oneshot(request)
+    }
+}
+
+fn boxed<S>(service: S) -> RouteService
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Error: Into<JsonRpcError>,
+    S::Future: Send + 'static,
+{
+    BoxCloneSyncService::new(service.map_err(Into::into))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use jsonrpsee_types::{ErrorCode, Id, Request, Response, ResponsePayload};
+    use serde_json::Value;
+    use tower::{ServiceExt, service_fn, util::BoxCloneSyncService};
+
+    use crate::router::{DuplicateMethod, RpcRouter};
+
+    fn echo(
+        name: &'static str,
+    ) -> BoxCloneSyncService<Request<'static>, Response<'static, Value>, Infallible> {
+        BoxCloneSyncService::new(service_fn(move |req: Request<'static>| async move {
+            Ok(Response::new(
+                ResponsePayload::success(Value::from(name)),
+                req.id,
+            ))
+        }))
+    }
+
+    #[tokio::test]
+    async fn routes_by_method_name() {
+        let router = RpcRouter::builder()
+            .route(["say_hello", "say_hi"], echo("say"))
+            .route(["admin_stop"], echo("admin"))
+            .build()
+            .unwrap();
+
+        for (method, expected) in [("say_hi", "say"), ("admin_stop", "admin")] {
+            let request = Request::owned(method.to_string(), None, Id::Number(1));
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert!(
+                matches!(response.payload, ResponsePayload::Success(value) if *value == expected)
+            );
+        }
+
+        let request = Request::owned("say_bye".to_string(), None, Id::Number(2));
+        let response = router.oneshot(request).await.unwrap();
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Error(err) if err.code() == ErrorCode::MethodNotFound.code()
+        ));
+    }
+
+    #[test]
+    fn duplicate_methods_are_rejected() {
+        let err = RpcRouter::builder()
+            .route(["say_hello"], echo("a"))
+            .route(["say_hello"], echo("b"))
+            .build()
+            .err();
+        assert_eq!(err, Some(DuplicateMethod("say_hello".to_string())));
+    }
+}
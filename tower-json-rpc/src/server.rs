@@ -7,7 +7,7 @@ use std::{
 };
 use tower::{Layer, Service};
 
-use crate::error::JsonRpcError;
+use crate::{cancel::DropGuard, error::JsonRpcError};
 
 pub trait ServerRequest: Send + 'static {
     type Response: ServerResponse;
@@ -21,6 +21,65 @@ pub trait ServerResponse: Sized + Send + 'static {
     fn from_json_rpc_response(
         response: Response<'static, Value>,
     ) -> Pin<Box<dyn Future<Output = Result<Self, JsonRpcError>> + Send + 'static>>;
+
+    /// Converts the response of a call to `method`, which [`RpcCall`](crate::client::RpcCall)
+    /// uses. Responses whose type depends on the method, like the generated `<Trait>Response`
+    /// enums, decode into the variant of `method` instead of guessing it from the result.
+    fn from_method_response(
+        method: &str,
+        response: Response<'static, Value>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self, JsonRpcError>> + Send + 'static>> {
+        let _ = method;
+        Self::from_json_rpc_response(response)
+    }
+}
+
+/// Marker inserted into the [`Response`] extensions when a `#[deprecated]` RPC method is called.
+///
+/// The HTTP [`ServerResponse`] impl copies it into the HTTP response extensions, so outer layers
+/// can log calls to deprecated methods. When `since` is a date, it also sets the RFC 9745
+/// `Deprecation` header, such as `Deprecation: @1719705600` for `since = "2024-06-30"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// Full name of the called RPC method, including the namespace.
+    pub method: &'static str,
+    /// The `since` argument of the `#[deprecated]` attribute.
+    pub since: Option<&'static str>,
+    /// The `note` argument of the `#[deprecated]` attribute.
+    pub note: Option<&'static str>,
+}
+
+impl Deprecation {
+    /// The Unix time of `since` when it is a `YYYY-MM-DD` date, rather than a version.
+    pub fn since_timestamp(&self) -> Option<i64> {
+        let mut parts = self.since?.splitn(3, '-');
+        let mut part = |len: usize| {
+            let part = parts.next().filter(|part| part.len() == len)?;
+            part.bytes()
+                .all(|byte| byte.is_ascii_digit())
+                .then(|| part.parse::<i64>().ok())?
+        };
+        let (year, month, day) = (part(4)?, part(2)?, part(2)?);
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+        if !(1..=days).contains(&day) {
+            return None;
+        }
+
+        // Days since the epoch of a proleptic Gregorian date, with years starting in March.
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some((era * 146_097 + day_of_era - 719_468) * 86_400)
+    }
 }
 
 /// A layer that maps http requests to JSON-RPC requests.
@@ -36,6 +95,9 @@ impl<S> Layer<S> for JsonRpcLayer {
 }
 
 /// JSON-RPC server that wraps an inner service
+///
+/// Every request gets a [`CancellationToken`](crate::cancel::CancellationToken) in its
+/// extensions, which is cancelled if the response future is dropped before the call completes.
 #[derive(Debug, Clone)]
 pub struct JsonRpcServer<S> {
     inner: S,
@@ -66,14 +128,17 @@ where
         let fut = request.into_json_rpc_request();
 
         Box::pin(fut.then(move |result| match result {
-            Ok(json_rpc_request) => {
+            Ok(mut json_rpc_request) => {
+                let guard = DropGuard::arm(&mut json_rpc_request);
+
                 let service_fut = service.call(json_rpc_request);
-                Box::pin(
-                    service_fut.then(move |service_result| match service_result {
+                Box::pin(service_fut.then(move |service_result| {
+                    guard.disarm();
+                    match service_result {
                         Ok(response) => Req::Response::from_json_rpc_response(response),
                         Err(e) => Box::pin(async move { Err(e.into()) }),
-                    }),
-                ) as BoxFuture<Result<Req::Response, JsonRpcError>>
+                    }
+                })) as BoxFuture<Result<Req::Response, JsonRpcError>>
             }
             Err(e) => Box::pin(async move { Err(e) }),
         }))
@@ -89,7 +154,10 @@ mod tests {
     use serde_json::Value;
     use tower::{ServiceBuilder, ServiceExt, service_fn};
 
-    use crate::server::JsonRpcLayer;
+    use crate::{
+        cancel::CancellationToken,
+        server::{Deprecation, JsonRpcLayer},
+    };
 
     #[tokio::test]
     async fn http_to_jsonrpc_roundtrip() {
@@ -127,4 +195,94 @@ mod tests {
         let response: Response<'_, Value> = serde_json::from_slice(&response_bytes).unwrap();
         assert!(matches!(response.payload, ResponsePayload::Success(_)));
     }
+
+    #[tokio::test]
+    async fn deprecated_method_sets_header() {
+        let svc = ServiceBuilder::new()
+            .layer(JsonRpcLayer)
+            .service(service_fn(|req: Request<'static>| async move {
+                let mut response = Response::new(ResponsePayload::success(Value::Null), req.id);
+                response.extensions_mut().insert(Deprecation {
+                    method: "say_hello",
+                    since: Some("2024-06-30"),
+                    note: None,
+                });
+                Ok::<_, std::convert::Infallible>(response)
+            }));
+
+        let json_request: Request<'static> =
+            Request::owned("say_hello".to_string(), None, Id::Number(1));
+        let http_request = http::Request::builder()
+            .method("POST")
+            .body(Full::new(Bytes::from(
+                serde_json::to_vec(&json_request).unwrap(),
+            )))
+            .unwrap();
+
+        let http_response = svc.oneshot(http_request).await.unwrap();
+        assert_eq!(http_response.headers()["deprecation"], "@1719705600");
+        assert_eq!(
+            http_response
+                .extensions()
+                .get::<Deprecation>()
+                .map(|deprecation| deprecation.method),
+            Some("say_hello")
+        );
+
+        // Versions aren't dates, so only the extension is set.
+        let deprecation = |since| Deprecation {
+            method: "say_hello",
+            since,
+            note: None,
+        };
+        assert_eq!(deprecation(Some("0.2.0")).since_timestamp(), None);
+        assert_eq!(deprecation(None).since_timestamp(), None);
+        assert_eq!(deprecation(Some("1970-01-01")).since_timestamp(), Some(0));
+        assert_eq!(
+            deprecation(Some("2000-03-01")).since_timestamp(),
+            Some(951_868_800)
+        );
+        assert_eq!(deprecation(Some("2024-02-31")).since_timestamp(), None);
+        assert_eq!(deprecation(Some("2023-02-29")).since_timestamp(), None);
+        assert_eq!(
+            deprecation(Some("2024-02-29")).since_timestamp(),
+            Some(1_709_164_800)
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_calls_are_cancelled() {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let sender = std::sync::Arc::new(std::sync::Mutex::new(Some(sender)));
+        let svc = ServiceBuilder::new()
+            .layer(JsonRpcLayer)
+            .service(service_fn(move |req: Request<'static>| {
+                let token = req.extensions.get::<CancellationToken>().cloned().unwrap();
+                sender.lock().unwrap().take().unwrap().send(token).unwrap();
+                async move {
+                    std::future::pending::<()>().await;
+                    Ok::<_, std::convert::Infallible>(Response::new(
+                        ResponsePayload::success(Value::Null),
+                        req.id,
+                    ))
+                }
+            }));
+
+        let json_request: Request<'static> =
+            Request::owned("debug_traceBlock".to_string(), None, Id::Number(1));
+        let http_request = http::Request::builder()
+            .method("POST")
+            .body(Full::new(Bytes::from(
+                serde_json::to_vec(&json_request).unwrap(),
+            )))
+            .unwrap();
+
+        let call = tokio::spawn(svc.oneshot(http_request));
+        let token = receiver.await.unwrap();
+        assert!(!token.is_cancelled());
+
+        call.abort();
+        let _ = call.await;
+        assert!(token.is_cancelled());
+    }
 }
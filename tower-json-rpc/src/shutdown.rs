@@ -0,0 +1,299 @@
+//! Graceful shutdown of a JSON-RPC server.
+//!
+//! A [`ShutdownHandle`] tracks the calls going through its [`ShutdownLayer`] and the
+//! subscriptions registered with [`ShutdownHandle::subscription`]. [`ShutdownHandle::shutdown`]
+//! makes the layer refuse new calls, signals the subscriptions through
+//! [`SubscriptionGuard::closing`], and waits up to a deadline for both to finish.
+//!
+//! The layer only signals the subscriptions: it doesn't send close notifications itself, as the
+//! generated servers don't serve subscriptions yet. Transports serving them send the
+//! notification when the signal fires:
+//!
+//! ```ignore
+//! let shutdown = ShutdownHandle::new();
+//! let app = ServiceBuilder::new()
+//!     .layer(JsonRpcLayer)
+//!     .layer(shutdown.layer())
+//!     .service(router);
+//!
+//! axum::serve(listener, app)
+//!     .with_graceful_shutdown(async move {
+//!         tokio::signal::ctrl_c().await.ok();
+//!         shutdown.shutdown(Duration::from_secs(30)).await;
+//!     })
+//!     .await?;
+//! ```
+
+use std::{
+    future::ready,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::FutureExt;
+use jsonrpsee_types::{ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tokio::sync::watch;
+use tower::{Layer, Service};
+
+use crate::server::BoxFuture;
+
+/// Error code of the default shutdown error.
+pub const SHUTTING_DOWN_CODE: i32 = -32002;
+
+/// The default error answered once the server is shutting down: `-32002 server shutting down`.
+pub fn shutting_down_error() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(SHUTTING_DOWN_CODE, "server shutting down", None::<()>)
+}
+
+#[derive(Debug, Default)]
+struct State {
+    shutting_down: bool,
+    calls: usize,
+    subscriptions: usize,
+}
+
+/// What was still running when [`ShutdownHandle::shutdown`] returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Calls that didn't finish before the deadline.
+    pub calls: usize,
+    /// Subscriptions that didn't close before the deadline.
+    pub subscriptions: usize,
+}
+
+impl ShutdownReport {
+    /// Whether every call and subscription finished before the deadline.
+    pub fn is_complete(&self) -> bool {
+        self.calls == 0 && self.subscriptions == 0
+    }
+}
+
+/// Starts the shutdown of the services created by its [`ShutdownLayer`]. Clones share the
+/// same state.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    state: watch::Sender<State>,
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self {
+            state: watch::Sender::new(State::default()),
+        }
+    }
+}
+
+impl ShutdownHandle {
+    /// Creates a handle of a running server.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A layer refusing calls once the shutdown started, and tracking the others.
+    pub fn layer(&self) -> ShutdownLayer {
+        ShutdownLayer {
+            state: self.state.clone(),
+            error: shutting_down_error(),
+        }
+    }
+
+    /// Tracks an active subscription until the returned guard is dropped.
+    pub fn subscription(&self) -> SubscriptionGuard {
+        self.state.send_modify(|state| state.subscriptions += 1);
+        SubscriptionGuard {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Whether the shutdown started.
+    pub fn is_shutting_down(&self) -> bool {
+        self.state.borrow().shutting_down
+    }
+
+    /// Refuses new calls, signals the subscriptions, and waits for the calls in flight and
+    /// the subscriptions to finish, for at most `deadline`.
+    pub async fn shutdown(&self, deadline: Duration) -> ShutdownReport {
+        self.state.send_modify(|state| state.shutting_down = true);
+
+        let mut state = self.state.subscribe();
+        let _ = tokio::time::timeout(
+            deadline,
+            state.wait_for(|state| state.calls == 0 && state.subscriptions == 0),
+        )
+        .await;
+
+        let state = self.state.borrow();
+        ShutdownReport {
+            calls: state.calls,
+            subscriptions: state.subscriptions,
+        }
+    }
+}
+
+/// An active subscription tracked by a [`ShutdownHandle`].
+///
+/// The guard doesn't send anything to the client. The task forwarding the notifications of
+/// the subscription waits on [`closing`](Self::closing) next to its stream, sends its own
+/// close notification when it completes, and drops the guard.
+#[derive(Debug)]
+pub struct SubscriptionGuard {
+    state: watch::Sender<State>,
+}
+
+impl SubscriptionGuard {
+    /// Completes once the shutdown started.
+    pub async fn closing(&self) {
+        let mut state = self.state.subscribe();
+        let _ = state.wait_for(|state| state.shutting_down).await;
+    }
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        self.state.send_modify(|state| state.subscriptions -= 1);
+    }
+}
+
+/// Tracks a call in flight until dropped.
+#[derive(Debug)]
+struct CallGuard {
+    state: watch::Sender<State>,
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        self.state.send_modify(|state| state.calls -= 1);
+    }
+}
+
+/// Layer created by [`ShutdownHandle::layer`].
+#[derive(Debug, Clone)]
+pub struct ShutdownLayer {
+    state: watch::Sender<State>,
+    error: ErrorObjectOwned,
+}
+
+impl ShutdownLayer {
+    /// Sets the error answered once the server is shutting down, instead of
+    /// [`shutting_down_error`].
+    pub fn error(mut self, error: ErrorObjectOwned) -> Self {
+        self.error = error;
+        self
+    }
+}
+
+impl<S> Layer<S> for ShutdownLayer {
+    type Service = ShutdownService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ShutdownService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`ShutdownLayer`].
+#[derive(Debug, Clone)]
+pub struct ShutdownService<S> {
+    inner: S,
+    layer: ShutdownLayer,
+}
+
+impl<S> Service<Request<'static>> for ShutdownService<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let accepted = self.layer.state.send_if_modified(|state| {
+            if !state.shutting_down {
+                state.calls += 1;
+            }
+            !state.shutting_down
+        });
+
+        if accepted {
+            let guard = CallGuard {
+                state: self.layer.state.clone(),
+            };
+            Box::pin(self.inner.call(request).map(move |result| {
+                drop(guard);
+                result
+            }))
+        } else {
+            Box::pin(ready(Ok(Response::new(
+                ResponsePayload::error(self.layer.error.clone()),
+                request.id,
+            ))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jsonrpsee_types::{Id, ResponsePayload};
+    use tower::Layer;
+
+    use crate::{
+        shutdown::{SHUTTING_DOWN_CODE, ShutdownHandle, ShutdownReport},
+        test_util::{call, request, slow_echo},
+    };
+
+    #[tokio::test]
+    async fn drains_calls_and_subscriptions() {
+        let handle = ShutdownHandle::new();
+        let svc = handle.layer().layer(slow_echo(Duration::from_millis(30)));
+        let call = |id| call(&svc, request("eth_call", id));
+
+        let in_flight = tokio::spawn(call(1));
+        let subscription = handle.subscription();
+        let closed = tokio::spawn(async move {
+            subscription.closing().await;
+            // The close notification would be sent here.
+        });
+        tokio::task::yield_now().await;
+
+        let shutdown = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.shutdown(Duration::from_secs(5)).await }
+        });
+        while !handle.is_shutting_down() {
+            tokio::task::yield_now().await;
+        }
+
+        let refused = call(2).await.unwrap();
+        assert_eq!(refused.id, Id::Number(2));
+        assert!(matches!(
+            refused.payload,
+            ResponsePayload::Error(err) if err.code() == SHUTTING_DOWN_CODE
+        ));
+
+        let response = in_flight.await.unwrap().unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+        closed.await.unwrap();
+        assert_eq!(shutdown.await.unwrap(), ShutdownReport::default());
+    }
+
+    #[tokio::test]
+    async fn reports_what_missed_the_deadline() {
+        let handle = ShutdownHandle::new();
+        let _subscription = handle.subscription();
+
+        let report = handle.shutdown(Duration::from_millis(10)).await;
+        assert!(!report.is_complete());
+        assert_eq!(report.subscriptions, 1);
+    }
+}
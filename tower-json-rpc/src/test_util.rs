@@ -0,0 +1,38 @@
+//! Services and requests shared by the tests of the layers.
+
+use std::{convert::Infallible, time::Duration};
+
+use jsonrpsee_types::{Id, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::{
+    Service, ServiceExt, service_fn,
+    util::{BoxCloneSyncService, Oneshot},
+};
+
+pub(crate) type Echo = BoxCloneSyncService<Request<'static>, Response<'static, Value>, Infallible>;
+
+/// A service answering every call with a `null` result.
+pub(crate) fn echo() -> Echo {
+    slow_echo(Duration::ZERO)
+}
+
+/// A service answering every call with a `null` result, after `delay`.
+pub(crate) fn slow_echo(delay: Duration) -> Echo {
+    Echo::new(service_fn(move |req: Request<'static>| async move {
+        tokio::time::sleep(delay).await;
+        Ok(Response::new(ResponsePayload::success(Value::Null), req.id))
+    }))
+}
+
+/// A call to `method` without params.
+pub(crate) fn request(method: &str, id: u64) -> Request<'static> {
+    Request::owned(method.to_string(), None, Id::Number(id))
+}
+
+/// Calls a clone of `service`, so that the tests can keep calling it.
+pub(crate) fn call<S>(service: &S, request: Request<'static>) -> Oneshot<S, Request<'static>>
+where
+    S: Service<Request<'static>> + Clone,
+{
+    service.clone().oneshot(request)
+}
@@ -0,0 +1,189 @@
+//! Timeouts answered with JSON-RPC errors.
+//!
+//! Unlike `tower::timeout`, which fails the service call with an `Elapsed` error, a timed out
+//! request is answered with an error response carrying the id of the request.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::FutureExt;
+use jsonrpsee_types::{ErrorObjectOwned, Request, Response, ResponsePayload};
+use serde_json::Value;
+use tower::{Layer, Service};
+
+use crate::{method::MethodInfo, server::BoxFuture};
+
+/// Error code of the default timeout error.
+pub const TIMEOUT_CODE: i32 = -32000;
+
+/// The default error returned when a request times out: `-32000 request timed out`.
+pub fn timeout_error() -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(TIMEOUT_CODE, "request timed out", None::<()>)
+}
+
+/// Runs `future` for at most `duration`, returning `None` if it didn't complete in time.
+pub async fn race<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+#[derive(Debug, Clone)]
+struct Timeouts {
+    default: Option<Duration>,
+    methods: HashMap<String, Duration>,
+    error: ErrorObjectOwned,
+}
+
+/// A layer that answers requests with a timeout error when the inner service takes too long.
+///
+/// ```ignore
+/// let layer = RpcTimeoutLayer::new()
+///     .default_timeout(Duration::from_secs(10))
+///     .methods(<SayService as RpcMethods>::INFO)
+///     .method("eth_call", Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RpcTimeoutLayer {
+    timeouts: Arc<Timeouts>,
+}
+
+impl Default for RpcTimeoutLayer {
+    fn default() -> Self {
+        Self {
+            timeouts: Arc::new(Timeouts {
+                default: None,
+                methods: HashMap::new(),
+                error: timeout_error(),
+            }),
+        }
+    }
+}
+
+impl RpcTimeoutLayer {
+    /// Creates a layer without any timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the timeout of the methods without their own timeout.
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        Arc::make_mut(&mut self.timeouts).default = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout of the method `name`.
+    pub fn method(mut self, name: impl Into<String>, timeout: Duration) -> Self {
+        Arc::make_mut(&mut self.timeouts)
+            .methods
+            .insert(name.into(), timeout);
+        self
+    }
+
+    /// Sets the timeouts declared with `#[method(timeout = "...")]`, including aliases.
+    pub fn methods(mut self, methods: &[MethodInfo]) -> Self {
+        let timeouts = Arc::make_mut(&mut self.timeouts);
+        for info in methods {
+            if let Some(timeout) = info.timeout {
+                timeouts
+                    .methods
+                    .extend(info.names().map(|name| (name.to_string(), timeout)));
+            }
+        }
+        self
+    }
+
+    /// Sets the error returned on timeouts, instead of [`timeout_error`].
+    pub fn error(mut self, error: ErrorObjectOwned) -> Self {
+        Arc::make_mut(&mut self.timeouts).error = error;
+        self
+    }
+}
+
+impl<S> Layer<S> for RpcTimeoutLayer {
+    type Service = RpcTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcTimeout {
+            inner,
+            timeouts: self.timeouts.clone(),
+        }
+    }
+}
+
+/// Service produced by [`RpcTimeoutLayer`].
+#[derive(Debug, Clone)]
+pub struct RpcTimeout<S> {
+    inner: S,
+    timeouts: Arc<Timeouts>,
+}
+
+impl<S> Service<Request<'static>> for RpcTimeout<S>
+where
+    S: Service<Request<'static>, Response = Response<'static, Value>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<'static>) -> Self::Future {
+        let timeout = self
+            .timeouts
+            .methods
+            .get(request.method.as_ref())
+            .copied()
+            .or(self.timeouts.default);
+        let id = request.id.clone();
+        let future = self.inner.call(request);
+
+        let Some(timeout) = timeout else {
+            return Box::pin(future);
+        };
+        let error = self.timeouts.error.clone();
+        Box::pin(
+            tokio::time::timeout(timeout, future).map(move |result| match result {
+                Ok(result) => result,
+                Err(_) => Ok(Response::new(ResponsePayload::error(error), id)),
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jsonrpsee_types::{Id, ResponsePayload};
+    use tower::Layer;
+
+    use crate::{
+        test_util::{call, request, slow_echo},
+        timeout::{RpcTimeoutLayer, TIMEOUT_CODE},
+    };
+
+    #[tokio::test]
+    async fn slow_methods_time_out() {
+        let svc = RpcTimeoutLayer::new()
+            .default_timeout(Duration::from_millis(10))
+            .method("trace_call", Duration::from_secs(60))
+            .layer(slow_echo(Duration::from_millis(50)));
+
+        let response = call(&svc, request("eth_call", 7)).await.unwrap();
+        assert_eq!(response.id, Id::Number(7));
+        assert!(matches!(
+            response.payload,
+            ResponsePayload::Error(err) if err.code() == TIMEOUT_CODE
+        ));
+
+        let response = call(&svc, request("trace_call", 8)).await.unwrap();
+        assert!(matches!(response.payload, ResponsePayload::Success(_)));
+    }
+}
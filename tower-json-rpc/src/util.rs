@@ -0,0 +1,12 @@
+//! Helpers shared by the layers and the client.
+
+use std::mem;
+
+/// Takes the service that was polled ready to call it, leaving a fresh clone in its place.
+///
+/// A clone isn't guaranteed to be ready, so a service calling its inner service from a
+/// `'static` future calls the one that answered `poll_ready`.
+pub(crate) fn take_ready<S: Clone>(service: &mut S) -> S {
+    let clone = service.clone();
+    mem::replace(service, clone)
+}
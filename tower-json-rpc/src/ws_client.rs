@@ -80,6 +80,7 @@ impl Service<Request<'static>> for WsClient {
 
             match result {
                 Ok(value) => Ok(Response::new(ResponsePayload::success(value), id)),
+                Err(ClientError::Call(err)) => Err(JsonRpcError::Rpc(err)),
                 Err(e) => Err(JsonRpcError::RequestProcessing(e.to_string())),
             }
         })